@@ -0,0 +1,2 @@
+-- Add migration script here
+CREATE INDEX IF NOT EXISTS currency_rate_currency_date ON currency_rate (currency_id, date);
@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS fetch_status
+(
+    id           INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    kind         TEXT                              NOT NULL,
+    ticker       TEXT                              NOT NULL,
+    provider     TEXT                              NOT NULL,
+    last_attempt TIMESTAMP                         NOT NULL,
+    last_success TIMESTAMP,
+    attempts     INTEGER                           NOT NULL,
+    error        TEXT,
+    UNIQUE (kind, ticker)
+);
@@ -0,0 +1,6 @@
+-- Add migration script here
+ALTER TABLE currency_rate ADD source TEXT NOT NULL DEFAULT 'manual';
+ALTER TABLE currency_rate ADD fetched_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE currency_rate ADD version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE currency_rate ADD superseded_at TIMESTAMP;
+UPDATE currency_rate SET fetched_at = date;
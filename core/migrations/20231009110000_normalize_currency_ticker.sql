@@ -0,0 +1,21 @@
+-- Add migration script here
+-- Currencies whose tickers normalize to the same code are merged into the oldest of them, so the
+-- base currency keeps id 1. Assets and rates of the others are moved to it.
+CREATE TEMP TABLE currency_merge AS
+SELECT dup.id AS old_id,
+       (SELECT MIN(keep.id)
+        FROM currency keep
+        WHERE UPPER(TRIM(keep.ticker)) = UPPER(TRIM(dup.ticker))) AS new_id
+FROM currency dup;
+DELETE FROM currency_merge WHERE old_id = new_id;
+
+UPDATE asset
+SET currency = (SELECT new_id FROM currency_merge WHERE old_id = asset.currency)
+WHERE currency IN (SELECT old_id FROM currency_merge);
+UPDATE currency_rate
+SET currency_id = (SELECT new_id FROM currency_merge WHERE old_id = currency_rate.currency_id)
+WHERE currency_id IN (SELECT old_id FROM currency_merge);
+DELETE FROM currency WHERE id IN (SELECT old_id FROM currency_merge);
+DROP TABLE currency_merge;
+
+UPDATE currency SET ticker = UPPER(TRIM(ticker));
@@ -0,0 +1,18 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS corporate_action
+(
+    id          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    action_type TEXT      NOT NULL,
+    action_date TIMESTAMP NOT NULL,
+    asset_id    INTEGER   NOT NULL,
+    target_id   INTEGER,
+    ratio       INTEGER   NOT NULL,
+    cost_basis  INTEGER   NOT NULL,
+    old_ticker  TEXT,
+    old_name    TEXT,
+    FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (target_id) REFERENCES asset (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS corporate_action_asset ON corporate_action (asset_id);
+CREATE INDEX IF NOT EXISTS corporate_action_target ON corporate_action (target_id);
@@ -0,0 +1,37 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS account
+(
+    id           INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name         TEXT UNIQUE                       NOT NULL,
+    broker       TEXT                              NOT NULL,
+    currency     INTEGER                           NOT NULL,
+    account_type TEXT                              NOT NULL,
+    FOREIGN KEY (currency) REFERENCES currency (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+INSERT INTO account (name, broker, currency, account_type)
+VALUES ('default', '', 1, 'Brokerage');
+
+-- SQLite can't add a REFERENCES column with a non-NULL default, so the table is rebuilt.
+CREATE TABLE asset_operations_new
+(
+    id               INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    asset_id         INTEGER   NOT NULL,
+    operation_type   TEXT      NOT NULL,
+    operation_date   TIMESTAMP NOT NULL,
+    operation_amount INTEGER   NOT NULL,
+    currency_rate    INTEGER   NOT NULL,
+    account          INTEGER   NOT NULL DEFAULT 1,
+    FOREIGN KEY (asset_id) REFERENCES asset (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (currency_rate) REFERENCES currency_rate (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (account) REFERENCES account (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+INSERT INTO asset_operations_new (id, asset_id, operation_type, operation_date, operation_amount, currency_rate)
+SELECT id, asset_id, operation_type, operation_date, operation_amount, currency_rate
+FROM asset_operations;
+
+DROP TABLE asset_operations;
+ALTER TABLE asset_operations_new RENAME TO asset_operations;
+
+CREATE INDEX IF NOT EXISTS asset_operations_account ON asset_operations (account);
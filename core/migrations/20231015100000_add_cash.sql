@@ -0,0 +1,32 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS cash_exchange
+(
+    id            INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    account       INTEGER   NOT NULL,
+    exchange_date TIMESTAMP NOT NULL,
+    from_currency INTEGER   NOT NULL,
+    from_amount   INTEGER   NOT NULL,
+    to_currency   INTEGER   NOT NULL,
+    to_amount     INTEGER   NOT NULL,
+    FOREIGN KEY (account) REFERENCES account (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (from_currency) REFERENCES currency (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (to_currency) REFERENCES currency (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS cash_operations
+(
+    id              INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    account         INTEGER   NOT NULL,
+    currency        INTEGER   NOT NULL,
+    operation_type  TEXT      NOT NULL,
+    operation_date  TIMESTAMP NOT NULL,
+    amount          INTEGER   NOT NULL,
+    asset_operation INTEGER,
+    exchange        INTEGER,
+    FOREIGN KEY (account) REFERENCES account (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (currency) REFERENCES currency (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (asset_operation) REFERENCES asset_operations (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (exchange) REFERENCES cash_exchange (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS cash_operations_account ON cash_operations (account, currency);
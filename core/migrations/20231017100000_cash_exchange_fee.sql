@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE cash_exchange ADD fee INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE cash_exchange ADD fee_currency INTEGER REFERENCES currency (id) ON DELETE CASCADE ON UPDATE CASCADE;
@@ -0,0 +1,40 @@
+-- Add migration script here
+-- Tickers become unique per exchange, which needs a table rebuild. Migrations run in a transaction where
+-- foreign keys can't be disabled and dropping the table cascades, so dependent rows are restored from copies.
+CREATE TABLE asset_new
+(
+    id               INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name             TEXT    NOT NULL,
+    ticker           TEXT    NOT NULL,
+    description      TEXT    NOT NULL,
+    currency         INTEGER NOT NULL,
+    exchange         TEXT    NOT NULL DEFAULT '',
+    isin             TEXT UNIQUE,
+    figi             TEXT UNIQUE,
+    cusip            TEXT,
+    instrument_class TEXT    NOT NULL DEFAULT 'Equity',
+    FOREIGN KEY (currency) REFERENCES currency (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (ticker, exchange)
+);
+
+INSERT INTO asset_new (id, name, ticker, description, currency)
+SELECT id, name, ticker, description, currency
+FROM asset;
+
+CREATE TEMP TABLE asset_to_type_copy AS SELECT * FROM asset_to_type;
+CREATE TEMP TABLE asset_operations_copy AS SELECT * FROM asset_operations;
+CREATE TEMP TABLE cash_operations_copy AS SELECT * FROM cash_operations WHERE asset_operation IS NOT NULL;
+CREATE TEMP TABLE corporate_action_copy AS SELECT * FROM corporate_action;
+
+DROP TABLE asset;
+ALTER TABLE asset_new RENAME TO asset;
+
+INSERT INTO asset_to_type SELECT * FROM asset_to_type_copy;
+INSERT INTO asset_operations SELECT * FROM asset_operations_copy;
+INSERT INTO cash_operations SELECT * FROM cash_operations_copy;
+INSERT INTO corporate_action SELECT * FROM corporate_action_copy;
+
+DROP TABLE asset_to_type_copy;
+DROP TABLE asset_operations_copy;
+DROP TABLE cash_operations_copy;
+DROP TABLE corporate_action_copy;
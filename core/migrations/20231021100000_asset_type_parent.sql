@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE asset_type ADD parent INTEGER REFERENCES asset_type (id);
+CREATE INDEX IF NOT EXISTS asset_type_parent ON asset_type (parent);
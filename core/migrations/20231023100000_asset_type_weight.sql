@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Share of the asset in the type, percent with two decimals.
+ALTER TABLE asset_to_type ADD weight INTEGER NOT NULL DEFAULT 10000;
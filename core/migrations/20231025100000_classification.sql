@@ -0,0 +1,43 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS classification
+(
+    id          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name        TEXT UNIQUE                       NOT NULL,
+    description TEXT                              NOT NULL
+);
+
+INSERT INTO classification (name, description)
+VALUES ('default', '');
+
+-- Type names become unique per classification, which needs a table rebuild. Dropping the table cascades
+-- to asset_to_type, so links are restored from a copy. Parents are restored after the rename because
+-- the new table references the dropped one.
+CREATE TABLE asset_type_new
+(
+    id             INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name           TEXT    NOT NULL,
+    description    TEXT    NOT NULL,
+    parent         INTEGER REFERENCES asset_type (id),
+    classification INTEGER NOT NULL DEFAULT 1,
+    FOREIGN KEY (classification) REFERENCES classification (id) ON DELETE CASCADE ON UPDATE CASCADE,
+    UNIQUE (classification, name)
+);
+
+INSERT INTO asset_type_new (id, name, description)
+SELECT id, name, description
+FROM asset_type;
+
+CREATE TEMP TABLE asset_type_parent_copy AS SELECT id, parent FROM asset_type WHERE parent IS NOT NULL;
+CREATE TEMP TABLE asset_to_type_copy AS SELECT * FROM asset_to_type;
+
+DROP TABLE asset_type;
+ALTER TABLE asset_type_new RENAME TO asset_type;
+CREATE INDEX IF NOT EXISTS asset_type_parent ON asset_type (parent);
+
+UPDATE asset_type
+SET parent = (SELECT parent FROM asset_type_parent_copy WHERE asset_type_parent_copy.id = asset_type.id)
+WHERE id IN (SELECT id FROM asset_type_parent_copy);
+INSERT INTO asset_to_type SELECT * FROM asset_to_type_copy;
+
+DROP TABLE asset_type_parent_copy;
+DROP TABLE asset_to_type_copy;
@@ -0,0 +1,9 @@
+-- Add migration script here
+-- Weight and band are percent with two decimals.
+CREATE TABLE IF NOT EXISTS target_allocation
+(
+    type_id INTEGER PRIMARY KEY NOT NULL,
+    weight  INTEGER             NOT NULL,
+    band    INTEGER             NOT NULL DEFAULT 500,
+    FOREIGN KEY (type_id) REFERENCES asset_type (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
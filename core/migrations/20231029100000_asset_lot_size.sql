@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Minimal tradable quantity, two decimals.
+ALTER TABLE asset ADD lot_size INTEGER NOT NULL DEFAULT 100;
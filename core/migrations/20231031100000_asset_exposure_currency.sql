@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Currency the asset value depends on, NULL if it is the trading currency.
+ALTER TABLE asset ADD exposure_currency INTEGER REFERENCES currency (id) ON DELETE SET NULL;
@@ -0,0 +1,32 @@
+-- Add migration script here
+-- Cash legs of asset operations recorded before cash tracking.
+-- Trades are priced at the last price update of the asset at their date.
+INSERT INTO cash_operations (account, currency, operation_type, operation_date, amount, asset_operation)
+SELECT account, currency, 'Asset', operation_date, amount, id
+FROM (SELECT op.id,
+             op.account,
+             rate.currency_id AS currency,
+             op.operation_date,
+             CASE op.operation_type
+                 WHEN 'Buy' THEN -(op.operation_amount * price.operation_amount / 100)
+                 WHEN 'Sell' THEN op.operation_amount * price.operation_amount / 100
+                 WHEN 'Dividend' THEN op.operation_amount
+                 WHEN 'Interest' THEN op.operation_amount
+                 WHEN 'Fee' THEN -op.operation_amount
+                 WHEN 'Tax' THEN -op.operation_amount
+                 WHEN 'CashDeposit' THEN -op.operation_amount
+                 WHEN 'CashWithdrawal' THEN op.operation_amount
+                 ELSE 0
+                 END AS amount
+      FROM asset_operations op
+               INNER JOIN currency_rate rate ON rate.id = op.currency_rate
+               LEFT JOIN asset_operations price ON price.id = (SELECT last.id
+                                                               FROM asset_operations last
+                                                               WHERE last.asset_id = op.asset_id
+                                                                 AND last.operation_type = 'UpdatePrice'
+                                                                 AND last.operation_date <= op.operation_date
+                                                               ORDER BY last.operation_date DESC, last.id DESC
+                                                               LIMIT 1)
+      WHERE NOT EXISTS (SELECT 1 FROM cash_operations cash WHERE cash.asset_operation = op.id))
+WHERE amount IS NOT NULL
+  AND amount != 0;
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Rates move from 2 to 8 decimals.
+UPDATE currency_rate SET rate = rate * 1000000;
@@ -0,0 +1,78 @@
+use crate::dao::model::account::{Account, AccountRow, AccountType};
+use crate::dao::model::currency::Currency;
+use color_eyre::eyre::Error;
+use sqlx::{Pool, Sqlite};
+
+#[derive(Clone)]
+pub struct AccountDao {
+    pool: Pool<Sqlite>,
+}
+
+impl AccountDao {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Account>, Error> {
+        let accounts = sqlx::query_as!(AccountRow, "SELECT * FROM account")
+            .fetch_all(&self.pool)
+            .await?;
+        accounts.into_iter().map(Account::try_from).collect()
+    }
+
+    pub async fn get_by_name(&self, name: &str) -> Result<Option<Account>, Error> {
+        let account = sqlx::query_as!(AccountRow, "SELECT * FROM account WHERE name = ?", name)
+            .fetch_optional(&self.pool)
+            .await?;
+        account.map(Account::try_from).transpose()
+    }
+
+    pub async fn add(
+        &self,
+        name: String,
+        broker: String,
+        currency: &Currency,
+        account_type: AccountType,
+    ) -> Result<Account, Error> {
+        let currency = currency.id;
+        let tp: String = account_type.into();
+        let id = sqlx::query!(
+            "INSERT INTO account (name, broker, currency, account_type) VALUES (?, ?, ?, ?)",
+            name,
+            broker,
+            currency,
+            tp
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(Account {
+            id,
+            name,
+            broker,
+            currency,
+            account_type,
+        })
+    }
+
+    pub async fn count_operations(&self, account: &Account) -> Result<i64, Error> {
+        let id = account.id;
+        let count = sqlx::query_scalar!(
+            "SELECT (SELECT COUNT(*) FROM asset_operations WHERE account = ?) + \
+            (SELECT COUNT(*) FROM cash_operations WHERE account = ?) AS \"count!: i64\"",
+            id,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    pub async fn remove(&self, account: &Account) -> Result<(), Error> {
+        let id = account.id;
+        sqlx::query!("DELETE FROM account WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,8 +1,16 @@
-use crate::dao::model::assets::{Asset, AssetType};
+use crate::dao::model::account::Account;
+use crate::dao::model::assets::{Asset, AssetToType, AssetType, Classification};
+use crate::dao::model::cash::CashOperationType;
+use crate::dao::model::corporate_action::{
+    CorporateAction, CorporateActionRow, CorporateActionType,
+};
 use crate::dao::model::currency::{Currency, CurrencyRate};
-use crate::dao::model::operations::{AssetOperation, OperationType};
+use crate::dao::model::operations::{AssetOperation, AssetOperationRow};
 use crate::service::decimal::Decimal;
+use crate::service::identifiers::AssetIdentifiers;
+use chrono::NaiveDateTime;
 use color_eyre::eyre::Error;
+use log::warn;
 use sqlx::{Pool, Sqlite};
 
 #[derive(Clone)]
@@ -25,14 +33,17 @@ impl AssetsDao {
         Ok(assets)
     }
 
+    /// Assets of the type and all of its descendants.
     pub async fn find_assets_with_type(&self, asset_type: &AssetType) -> Result<Vec<Asset>, Error> {
         let id = asset_type.id;
         let assets = sqlx::query_as!(
             Asset,
             "\
-        SELECT id, name, ticker, description, currency FROM asset \
+        WITH RECURSIVE subtree(id) AS \
+        (SELECT ? UNION SELECT asset_type.id FROM asset_type INNER JOIN subtree ON asset_type.parent = subtree.id) \
+        SELECT DISTINCT asset.* FROM asset \
         INNER JOIN asset_to_type ON asset.id = asset_to_type.asset_id \
-        WHERE asset_to_type.type_id = ?",
+        WHERE asset_to_type.type_id IN (SELECT id FROM subtree)",
             id
         )
         .fetch_all(&self.pool)
@@ -40,6 +51,25 @@ impl AssetsDao {
         Ok(assets)
     }
 
+    /// Assets linked to the type itself.
+    pub async fn count_type_assets(&self, asset_type: &AssetType) -> Result<i64, Error> {
+        let id = asset_type.id;
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) AS \"count: i64\" FROM asset_to_type WHERE type_id = ?",
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    pub async fn get_type_links(&self) -> Result<Vec<AssetToType>, Error> {
+        let links = sqlx::query_as!(AssetToType, "SELECT * FROM asset_to_type")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(links)
+    }
+
     pub async fn get_types(&self) -> Result<Vec<AssetType>, Error> {
         let types = sqlx::query_as!(AssetType, "SELECT * FROM asset_type")
             .fetch_all(&self.pool)
@@ -47,11 +77,20 @@ impl AssetsDao {
         Ok(types)
     }
 
-    pub async fn add_type(&self, name: String, description: String) -> Result<AssetType, Error> {
+    pub async fn add_type(
+        &self,
+        name: String,
+        description: String,
+        parent: Option<i64>,
+        classification: &Classification,
+    ) -> Result<AssetType, Error> {
+        let classification = classification.id;
         let id = sqlx::query!(
-            "INSERT INTO asset_type (name, description) VALUES (?, ?)",
+            "INSERT INTO asset_type (name, description, parent, classification) VALUES (?, ?, ?, ?)",
             name,
-            description
+            description,
+            parent,
+            classification
         )
         .execute(&self.pool)
         .await?
@@ -61,9 +100,105 @@ impl AssetsDao {
             id,
             name,
             description,
+            parent,
+            classification,
         })
     }
 
+    pub async fn get_classifications(&self) -> Result<Vec<Classification>, Error> {
+        let classifications = sqlx::query_as!(Classification, "SELECT * FROM classification")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(classifications)
+    }
+
+    pub async fn get_classification_by_name(
+        &self,
+        name: &str,
+    ) -> Result<Option<Classification>, Error> {
+        let classification = sqlx::query_as!(
+            Classification,
+            "SELECT * FROM classification WHERE name = ?",
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(classification)
+    }
+
+    pub async fn add_classification(
+        &self,
+        name: String,
+        description: String,
+    ) -> Result<Classification, Error> {
+        let id = sqlx::query!(
+            "INSERT INTO classification (name, description) VALUES (?, ?)",
+            name,
+            description
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(Classification {
+            id,
+            name,
+            description,
+        })
+    }
+
+    pub async fn remove_classification(
+        &self,
+        classification: &Classification,
+    ) -> Result<(), Error> {
+        let id = classification.id;
+        sqlx::query!("DELETE FROM classification WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_type_parent(&self, tp: &AssetType, parent: Option<i64>) -> Result<(), Error> {
+        let id = tp.id;
+        sqlx::query!("UPDATE asset_type SET parent = ? WHERE id = ?", parent, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves children to the type parent and removes the type.
+    pub async fn remove_type_lifting_children(&self, tp: &AssetType) -> Result<(), Error> {
+        let id = tp.id;
+        let parent = tp.parent;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "UPDATE asset_type SET parent = ? WHERE parent = ?",
+            parent,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("DELETE FROM asset_type WHERE id = ?", id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes the type with all of its descendants.
+    pub async fn remove_type_subtree(&self, tp: &AssetType) -> Result<(), Error> {
+        let id = tp.id;
+        sqlx::query!(
+            "\
+        WITH RECURSIVE subtree(id) AS \
+        (SELECT ? UNION SELECT asset_type.id FROM asset_type INNER JOIN subtree ON asset_type.parent = subtree.id) \
+        DELETE FROM asset_type WHERE id IN (SELECT id FROM subtree)",
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn get_type(&self, id: i64) -> Result<Option<AssetType>, Error> {
         let types = sqlx::query_as!(AssetType, "SELECT * FROM asset_type WHERE id = ?", id)
             .fetch_optional(&self.pool)
@@ -71,17 +206,57 @@ impl AssetsDao {
         Ok(types)
     }
 
-    pub async fn get_type_by_name(&self, name: String) -> Result<Option<AssetType>, Error> {
+    /// Types with the name in any classification.
+    pub async fn find_types_by_name(&self, name: &str) -> Result<Vec<AssetType>, Error> {
         let types = sqlx::query_as!(AssetType, "SELECT * FROM asset_type WHERE name = ?", name)
-            .fetch_optional(&self.pool)
+            .fetch_all(&self.pool)
             .await?;
         Ok(types)
     }
 
-    pub async fn get_asset_by_ticker(&self, ticker: String) -> Result<Option<Asset>, Error> {
+    /// Assets with the ticker on any exchange.
+    pub async fn find_assets_by_ticker(&self, ticker: &str) -> Result<Vec<Asset>, Error> {
         let assets = sqlx::query_as!(Asset, "SELECT * FROM asset WHERE ticker = ?", ticker)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(assets)
+    }
+
+    pub async fn get_asset_by_listing(
+        &self,
+        ticker: &str,
+        exchange: &str,
+    ) -> Result<Option<Asset>, Error> {
+        let asset = sqlx::query_as!(
+            Asset,
+            "SELECT * FROM asset WHERE ticker = ? AND exchange = ?",
+            ticker,
+            exchange
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(asset)
+    }
+
+    pub async fn get_asset_by_isin(&self, isin: &str) -> Result<Option<Asset>, Error> {
+        let asset = sqlx::query_as!(Asset, "SELECT * FROM asset WHERE isin = ?", isin)
             .fetch_optional(&self.pool)
             .await?;
+        Ok(asset)
+    }
+
+    pub async fn get_asset_by_figi(&self, figi: &str) -> Result<Option<Asset>, Error> {
+        let asset = sqlx::query_as!(Asset, "SELECT * FROM asset WHERE figi = ?", figi)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(asset)
+    }
+
+    /// CUSIP is not unique across share classes history, so all matches are returned.
+    pub async fn find_assets_by_cusip(&self, cusip: &str) -> Result<Vec<Asset>, Error> {
+        let assets = sqlx::query_as!(Asset, "SELECT * FROM asset WHERE cusip = ?", cusip)
+            .fetch_all(&self.pool)
+            .await?;
         Ok(assets)
     }
 
@@ -105,7 +280,7 @@ impl AssetsDao {
         let types = sqlx::query_as!(
             AssetType,
             "\
-        SELECT id, name, description FROM asset_type \
+        SELECT asset_type.* FROM asset_type \
         INNER JOIN asset_to_type ON asset_type.id = asset_to_type.type_id \
         WHERE asset_to_type.asset_id = ?",
             id
@@ -121,14 +296,21 @@ impl AssetsDao {
         name: String,
         description: String,
         currency: &Currency,
+        ids: AssetIdentifiers,
     ) -> Result<Asset, Error> {
         let currency = currency.id;
+        let instrument_class: String = ids.instrument_class.into();
         let id = sqlx::query!(
-            "INSERT INTO asset (ticker, name, description, currency) VALUES (?, ?, ?, ?)",
+            "INSERT INTO asset (ticker, name, description, currency, exchange, isin, figi, cusip, instrument_class) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
             ticker,
             name,
             description,
-            currency
+            currency,
+            ids.exchange,
+            ids.isin,
+            ids.figi,
+            ids.cusip,
+            instrument_class
         )
         .execute(&self.pool)
         .await?
@@ -139,22 +321,137 @@ impl AssetsDao {
             name,
             description,
             currency,
+            exchange: ids.exchange,
+            isin: ids.isin,
+            figi: ids.figi,
+            cusip: ids.cusip,
+            instrument_class,
+            lot_size: Decimal::int(1),
+            exposure_currency: None,
+        })
+    }
+
+    pub async fn set_lot_size(&self, asset: &Asset, lot_size: Decimal) -> Result<(), Error> {
+        let id = asset.id;
+        let lot_size: i64 = lot_size.into();
+        sqlx::query!("UPDATE asset SET lot_size = ? WHERE id = ?", lot_size, id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_exposure_currency(
+        &self,
+        asset: &Asset,
+        currency: Option<&Currency>,
+    ) -> Result<(), Error> {
+        let id = asset.id;
+        let currency = currency.map(|c| c.id);
+        sqlx::query!(
+            "UPDATE asset SET exposure_currency = ? WHERE id = ?",
+            currency,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_identifiers(
+        &self,
+        asset: &Asset,
+        ids: AssetIdentifiers,
+    ) -> Result<Asset, Error> {
+        let id = asset.id;
+        let instrument_class: String = ids.instrument_class.into();
+        sqlx::query!(
+            "UPDATE asset SET exchange = ?, isin = ?, figi = ?, cusip = ?, instrument_class = ? WHERE id = ?",
+            ids.exchange,
+            ids.isin,
+            ids.figi,
+            ids.cusip,
+            instrument_class,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(Asset {
+            exchange: ids.exchange,
+            isin: ids.isin,
+            figi: ids.figi,
+            cusip: ids.cusip,
+            instrument_class,
+            ..asset.clone()
         })
     }
 
-    pub async fn add_asset_type(&self, asset: &Asset, tp: &AssetType) -> Result<(), Error> {
+    /// Links the asset to the type or updates the weight of an existing link.
+    pub async fn add_asset_type(
+        &self,
+        asset: &Asset,
+        tp: &AssetType,
+        weight: Decimal,
+    ) -> Result<(), Error> {
         let asset_id = asset.id;
         let type_id = tp.id;
+        let weight: i64 = weight.into();
         sqlx::query!(
-            "INSERT INTO asset_to_type (asset_id, type_id) VALUES (?, ?)",
+            "INSERT INTO asset_to_type (asset_id, type_id, weight) VALUES (?, ?, ?) \
+            ON CONFLICT (asset_id, type_id) DO UPDATE SET weight = excluded.weight",
             asset_id,
-            type_id
+            type_id,
+            weight
         )
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
+    /// Replaces the asset links to the `remove` types with the `add` links.
+    pub async fn replace_asset_types(
+        &self,
+        asset: &Asset,
+        remove: &[i64],
+        add: &[(i64, Decimal)],
+    ) -> Result<(), Error> {
+        let asset_id = asset.id;
+        let mut tx = self.pool.begin().await?;
+        for type_id in remove {
+            sqlx::query!(
+                "DELETE FROM asset_to_type WHERE asset_id = ? AND type_id = ?",
+                asset_id,
+                type_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        for (type_id, weight) in add {
+            let weight: i64 = (*weight).into();
+            sqlx::query!(
+                "INSERT INTO asset_to_type (asset_id, type_id, weight) VALUES (?, ?, ?)",
+                asset_id,
+                type_id,
+                weight
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_asset_links(&self, asset: &Asset) -> Result<Vec<AssetToType>, Error> {
+        let id = asset.id;
+        let links = sqlx::query_as!(
+            AssetToType,
+            "SELECT * FROM asset_to_type WHERE asset_id = ?",
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(links)
+    }
+
     pub async fn remove_asset_type(&self, asset: &Asset, tp: &AssetType) -> Result<(), Error> {
         let asset_id = asset.id;
         let type_id = tp.id;
@@ -176,38 +473,260 @@ impl AssetsDao {
         Ok(())
     }
 
-    pub async fn add_operation(
+    /// Writes the operations with the cash they move in one transaction.
+    /// Cash is moved in the currency of the operation rate.
+    pub async fn add_operations(
+        &self,
+        operations: &[(AssetOperation, Decimal)],
+    ) -> Result<Vec<i64>, Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut ids = Vec::new();
+        for (operation, cash) in operations {
+            let account = operation.account;
+            let asset_id = operation.asset_id;
+            let operation_type: String = operation.operation_type.into();
+            let operation_date = operation.operation_date;
+            let operation_amount: i64 = operation.operation_amount.into();
+            let currency_rate = operation.currency_rate;
+            let id = sqlx::query!(
+                "INSERT INTO asset_operations (asset_id, operation_type, operation_date, operation_amount, currency_rate, account) VALUES (?, ?, ?, ?, ?, ?)",
+                asset_id,
+                operation_type,
+                operation_date,
+                operation_amount,
+                currency_rate,
+                account
+            )
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+            if *cash != Decimal::default() {
+                let cash_type: String = CashOperationType::Asset.into();
+                let cash: i64 = (*cash).into();
+                sqlx::query!(
+                    "INSERT INTO cash_operations (account, currency, operation_type, operation_date, amount, asset_operation) \
+                    SELECT ?, currency_id, ?, ?, ?, ? FROM currency_rate WHERE id = ?",
+                    account,
+                    cash_type,
+                    operation_date,
+                    cash,
+                    id,
+                    currency_rate
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            ids.push(id);
+        }
+        tx.commit().await?;
+        Ok(ids)
+    }
+
+    pub async fn get_operations(&self, asset: &Asset) -> Result<Vec<AssetOperation>, Error> {
+        let asset_id = asset.id;
+        let operations = sqlx::query_as!(
+            AssetOperationRow,
+            "SELECT * FROM asset_operations WHERE asset_id = ?",
+            asset_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(known_operations(operations))
+    }
+
+    /// Operations booked to the account. Price updates of all accounts are included.
+    pub async fn get_account_operations(
         &self,
         asset: &Asset,
-        asset_rate: &CurrencyRate,
-        operation_type: OperationType,
-        operation_amount: Decimal,
-    ) -> Result<(), Error> {
+        account: &Account,
+    ) -> Result<Vec<AssetOperation>, Error> {
         let asset_id = asset.id;
-        let operation_type: String = operation_type.into();
-        let currency_rate = asset_rate.id;
-        let operation_amount: i64 = operation_amount.into();
-        sqlx::query!(
-            "INSERT INTO asset_operations (asset_id, operation_type, operation_date, operation_amount, currency_rate) VALUES (?, ?, datetime('now'), ?, ?)",
+        let account = account.id;
+        let operations = sqlx::query_as!(
+            AssetOperationRow,
+            "SELECT * FROM asset_operations WHERE asset_id = ? AND (account = ? OR operation_type = 'UpdatePrice')",
             asset_id,
-            operation_type,
-            operation_amount,
-            currency_rate
+            account
         )
-            .execute(&self.pool)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(known_operations(operations))
+    }
+
+    /// Currency rates recorded on the asset operations, including superseded ones.
+    pub async fn get_operation_rates(&self, asset: &Asset) -> Result<Vec<CurrencyRate>, Error> {
+        let id = asset.id;
+        let rates = sqlx::query_as!(
+            CurrencyRate,
+            "SELECT DISTINCT currency_rate.* FROM currency_rate \
+            INNER JOIN asset_operations ON asset_operations.currency_rate = currency_rate.id \
+            WHERE asset_operations.asset_id = ?",
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rates)
+    }
+
+    /// Assets with operations other than price updates in the account.
+    pub async fn find_assets_in_account(&self, account: &Account) -> Result<Vec<Asset>, Error> {
+        let account = account.id;
+        let assets = sqlx::query_as!(
+            Asset,
+            "SELECT * FROM asset WHERE id IN \
+            (SELECT asset_id FROM asset_operations WHERE account = ? AND operation_type != 'UpdatePrice')",
+            account
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(assets)
+    }
+
+    pub async fn get_asset(&self, id: i64) -> Result<Option<Asset>, Error> {
+        let asset = sqlx::query_as!(Asset, "SELECT * FROM asset WHERE id = ?", id)
+            .fetch_optional(&self.pool)
             .await?;
-        Ok(())
+        Ok(asset)
     }
 
-    pub async fn get_operations(&self, asset: &Asset) -> Result<Vec<AssetOperation>, Error> {
+    /// Renames the asset and records the old ticker and name.
+    pub async fn change_ticker(
+        &self,
+        asset: &Asset,
+        ticker: String,
+        name: String,
+        date: NaiveDateTime,
+    ) -> Result<Asset, Error> {
+        let id = asset.id;
+        let action_type: String = CorporateActionType::TickerChange.into();
+        let ratio: i64 = Decimal::int(1).into();
+        let cost_basis: i64 = Decimal::default().into();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "INSERT INTO corporate_action (action_type, action_date, asset_id, ratio, cost_basis, old_ticker, old_name) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            action_type,
+            date,
+            id,
+            ratio,
+            cost_basis,
+            asset.ticker,
+            asset.name
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "UPDATE asset SET ticker = ?, name = ? WHERE id = ?",
+            ticker,
+            name,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Asset {
+            name,
+            ticker,
+            ..asset.clone()
+        })
+    }
+
+    pub async fn add_corporate_action(
+        &self,
+        action_type: CorporateActionType,
+        date: NaiveDateTime,
+        asset: &Asset,
+        target: &Asset,
+        ratio: Decimal,
+        cost_basis: Decimal,
+    ) -> Result<i64, Error> {
+        let action_type: String = action_type.into();
         let asset_id = asset.id;
-        let operations = sqlx::query_as!(
-            AssetOperation,
-            "SELECT * FROM asset_operations WHERE asset_id = ?",
-            asset_id
+        let target_id = target.id;
+        let ratio: i64 = ratio.into();
+        let cost_basis: i64 = cost_basis.into();
+        let id = sqlx::query!(
+            "INSERT INTO corporate_action (action_type, action_date, asset_id, target_id, ratio, cost_basis) VALUES (?, ?, ?, ?, ?, ?)",
+            action_type,
+            date,
+            asset_id,
+            target_id,
+            ratio,
+            cost_basis
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Actions where the asset is the source or the target.
+    pub async fn get_corporate_actions(
+        &self,
+        asset: &Asset,
+    ) -> Result<Vec<CorporateAction>, Error> {
+        let id = asset.id;
+        let actions = sqlx::query_as!(
+            CorporateActionRow,
+            "SELECT * FROM corporate_action WHERE asset_id = ? OR target_id = ? ORDER BY action_date, id",
+            id,
+            id
         )
         .fetch_all(&self.pool)
         .await?;
-        Ok(operations)
+        actions.into_iter().map(CorporateAction::try_from).collect()
+    }
+}
+
+/// Skips rows of unknown operation type, so one bad row doesn't hide the whole history.
+fn known_operations(rows: Vec<AssetOperationRow>) -> Vec<AssetOperation> {
+    rows.into_iter()
+        .filter_map(|row| match AssetOperation::try_from(row) {
+            Ok(operation) => Some(operation),
+            Err(err) => {
+                warn!("skipping operation: {}", err);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::model::operations::OperationType;
+    use crate::dao::test_pool;
+    use crate::service::decimal::dec;
+    use crate::service::FinanceService;
+
+    #[tokio::test]
+    async fn test_get_operations_skips_unknown_type() {
+        let pool = test_pool().await;
+        let finance = FinanceService::new(pool.clone());
+        finance
+            .add_asset(
+                "AAPL".to_string(),
+                None,
+                None,
+                "USD".to_string(),
+                AssetIdentifiers::default(),
+            )
+            .await
+            .unwrap();
+        finance
+            .add_operation("AAPL".to_string(), dec("100"), OperationType::UpdatePrice)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO asset_operations (asset_id, operation_type, operation_date, operation_amount, currency_rate, account) \
+             SELECT asset_id, 'Bogus', operation_date, operation_amount, currency_rate, account FROM asset_operations",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let asset = finance.find_asset("AAPL").await.unwrap().unwrap();
+        let operations = AssetsDao::new(pool).get_operations(&asset).await.unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].operation_type, OperationType::UpdatePrice);
     }
 }
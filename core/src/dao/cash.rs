@@ -0,0 +1,300 @@
+use crate::dao::model::account::Account;
+use crate::dao::model::cash::{
+    CashBalance, CashExchange, CashOperation, CashOperationRow, CashOperationType,
+};
+use crate::dao::model::currency::Currency;
+use crate::service::decimal::Decimal;
+use chrono::NaiveDateTime;
+use color_eyre::eyre::Error;
+use sqlx::{Pool, Sqlite};
+
+#[derive(Clone)]
+pub struct CashDao {
+    pool: Pool<Sqlite>,
+}
+
+impl CashDao {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn add_operation(
+        &self,
+        account: &Account,
+        currency: &Currency,
+        operation_type: CashOperationType,
+        date: NaiveDateTime,
+        amount: Decimal,
+    ) -> Result<i64, Error> {
+        let account = account.id;
+        let currency = currency.id;
+        let operation_type: String = operation_type.into();
+        let amount: i64 = amount.into();
+        let id = sqlx::query!(
+            "INSERT INTO cash_operations (account, currency, operation_type, operation_date, amount) VALUES (?, ?, ?, ?, ?)",
+            account,
+            currency,
+            operation_type,
+            date,
+            amount
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Writes the exchange with its debit, credit and fee legs.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_exchange(
+        &self,
+        account: &Account,
+        date: NaiveDateTime,
+        from: &Currency,
+        from_amount: Decimal,
+        to: &Currency,
+        to_amount: Decimal,
+        fee: Option<(Decimal, &Currency)>,
+    ) -> Result<CashExchange, Error> {
+        let account = account.id;
+        let from_currency = from.id;
+        let to_currency = to.id;
+        let fee_currency = fee.map(|(_, currency)| currency.id);
+        let fee = fee.map(|(fee, _)| fee).unwrap_or_default();
+        let debit: i64 = (Decimal::default() - from_amount).into();
+        let credit: i64 = to_amount.into();
+        let from_value: i64 = from_amount.into();
+        let fee_value: i64 = fee.into();
+        let fee_debit: i64 = (Decimal::default() - fee).into();
+        let operation_type: String = CashOperationType::Exchange.into();
+
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query!(
+            "INSERT INTO cash_exchange (account, exchange_date, from_currency, from_amount, to_currency, to_amount, fee, fee_currency) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            account,
+            date,
+            from_currency,
+            from_value,
+            to_currency,
+            credit,
+            fee_value,
+            fee_currency
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        sqlx::query!(
+            "INSERT INTO cash_operations (account, currency, operation_type, operation_date, amount, exchange) VALUES (?, ?, ?, ?, ?, ?), (?, ?, ?, ?, ?, ?)",
+            account,
+            from_currency,
+            operation_type,
+            date,
+            debit,
+            id,
+            account,
+            to_currency,
+            operation_type,
+            date,
+            credit,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if let Some(fee_currency) = fee_currency {
+            sqlx::query!(
+                "INSERT INTO cash_operations (account, currency, operation_type, operation_date, amount, exchange) VALUES (?, ?, ?, ?, ?, ?)",
+                account,
+                fee_currency,
+                operation_type,
+                date,
+                fee_debit,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(CashExchange {
+            id,
+            account,
+            exchange_date: date,
+            from_currency,
+            from_amount,
+            to_currency,
+            to_amount,
+            fee,
+            fee_currency,
+        })
+    }
+
+    pub async fn get_exchanges(&self, account: &Account) -> Result<Vec<CashExchange>, Error> {
+        let account = account.id;
+        let exchanges = sqlx::query_as!(
+            CashExchange,
+            "SELECT * FROM cash_exchange WHERE account = ? ORDER BY exchange_date, id",
+            account
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(exchanges)
+    }
+
+    pub async fn get_operations(&self, account: &Account) -> Result<Vec<CashOperation>, Error> {
+        let account = account.id;
+        let operations = sqlx::query_as!(
+            CashOperationRow,
+            "SELECT * FROM cash_operations WHERE account = ? ORDER BY operation_date, id",
+            account
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        operations
+            .into_iter()
+            .map(CashOperation::try_from)
+            .collect()
+    }
+
+    pub async fn balances(&self) -> Result<Vec<CashBalance>, Error> {
+        let balances = sqlx::query_as!(
+            CashBalance,
+            "SELECT account, currency, SUM(amount) AS \"balance!: i64\" FROM cash_operations GROUP BY account, currency"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(balances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::currency::CurrencyDao;
+    use crate::dao::model::account::DEFAULT_ACCOUNT;
+    use crate::dao::model::operations::OperationType;
+    use crate::dao::{test_pool, test_pool_before};
+    use crate::service::decimal::{dec, Rate};
+    use crate::service::identifiers::AssetIdentifiers;
+    use crate::service::test_utils::time;
+    use crate::service::FinanceService;
+
+    async fn balance(dao: &CashDao) -> Decimal {
+        let balances = dao.balances().await.unwrap();
+        assert_eq!(balances.len(), 1);
+        balances[0].balance
+    }
+
+    #[tokio::test]
+    async fn test_cash_legs() {
+        let pool = test_pool().await;
+        let finance = FinanceService::new(pool.clone());
+        let dao = CashDao::new(pool.clone());
+        CurrencyDao::new(pool.clone())
+            .add_rate_on(1, Rate::int(1), time("2020-01-01 00:00:00"), "manual")
+            .await
+            .unwrap();
+        finance
+            .add_asset(
+                "AAPL".to_string(),
+                None,
+                None,
+                "USD".to_string(),
+                AssetIdentifiers::default(),
+            )
+            .await
+            .unwrap();
+
+        finance
+            .deposit_cash(
+                DEFAULT_ACCOUNT,
+                "USD",
+                dec("1000"),
+                time("2020-01-01 00:00:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(balance(&dao).await, dec("1000"));
+
+        for (tp, amount, expected) in [
+            (OperationType::UpdatePrice, "100", "1000"),
+            (OperationType::Buy, "2", "800"),
+            (OperationType::Fee, "1.5", "798.5"),
+            (OperationType::Sell, "1", "898.5"),
+            (OperationType::Dividend, "3", "901.5"),
+        ] {
+            finance
+                .add_operation_on(
+                    DEFAULT_ACCOUNT,
+                    "AAPL".to_string(),
+                    dec(amount),
+                    tp,
+                    time("2020-01-02 00:00:00"),
+                )
+                .await
+                .unwrap();
+            assert_eq!(balance(&dao).await, dec(expected), "after {:?}", tp);
+        }
+
+        finance
+            .withdraw_cash(
+                DEFAULT_ACCOUNT,
+                "USD",
+                dec("100"),
+                time("2020-01-03 00:00:00"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(balance(&dao).await, dec("801.5"));
+
+        let account = finance.account.default_account().await.unwrap();
+        let legs = dao.get_operations(&account).await.unwrap();
+        assert_eq!(
+            legs.iter()
+                .map(|l| (l.operation_type, l.asset_operation.is_some()))
+                .collect::<Vec<_>>(),
+            vec![
+                (CashOperationType::Deposit, false),
+                (CashOperationType::Asset, true),
+                (CashOperationType::Asset, true),
+                (CashOperationType::Asset, true),
+                (CashOperationType::Asset, true),
+                (CashOperationType::Withdrawal, false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_cash_operations_migration() {
+        let pool = test_pool_before(20231102100000).await;
+        for query in [
+            "INSERT INTO currency_rate (id, currency_id, rate, date) VALUES (2, 1, 100, '2020-01-01 00:00:00')",
+            "INSERT INTO asset (id, name, ticker, description, currency) VALUES (1, 'Apple', 'AAPL', '', 1)",
+            "INSERT INTO asset_operations (id, asset_id, operation_type, operation_date, operation_amount, currency_rate) VALUES \
+             (1, 1, 'UpdatePrice', '2020-01-01 00:00:00', 10000, 2), \
+             (2, 1, 'Buy', '2020-01-02 00:00:00', 200, 2), \
+             (3, 1, 'Fee', '2020-01-02 00:00:00', 150, 2), \
+             (4, 1, 'UpdatePrice', '2020-01-03 00:00:00', 12000, 2), \
+             (5, 1, 'Sell', '2020-01-04 00:00:00', 100, 2), \
+             (6, 1, 'Dividend', '2020-01-04 00:00:00', 300, 2), \
+             (7, 1, 'Buy', '2020-01-05 00:00:00', 100, 2)",
+            // Operations recorded after cash tracking already have their leg.
+            "INSERT INTO cash_operations (account, currency, operation_type, operation_date, amount, asset_operation) \
+             VALUES (1, 1, 'Asset', '2020-01-05 00:00:00', -12000, 7)",
+        ] {
+            sqlx::query(query).execute(&pool).await.unwrap();
+        }
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        let legs: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT asset_operation, amount FROM cash_operations ORDER BY asset_operation",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            legs,
+            vec![(2, -20000), (3, -150), (5, 12000), (6, 300), (7, -12000)]
+        );
+        assert_eq!(balance(&CashDao::new(pool)).await, dec("-198.5"));
+    }
+}
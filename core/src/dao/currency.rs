@@ -1,5 +1,6 @@
 use crate::dao::model::currency::{Currency, CurrencyRate};
-use crate::service::decimal::Decimal;
+use crate::service::decimal::Rate;
+use chrono::NaiveDateTime;
 use color_eyre::eyre::Error;
 use log::debug;
 use sqlx::{Pool, Sqlite};
@@ -57,12 +58,13 @@ impl CurrencyDao {
         Ok(())
     }
 
-    pub async fn add_rate(&self, currency_id: i64, rate: Decimal) -> Result<i64, Error> {
+    pub async fn add_rate(&self, currency_id: i64, rate: Rate, source: &str) -> Result<i64, Error> {
         let rate: i64 = rate.into();
         let id = sqlx::query!(
-            "INSERT INTO currency_rate (currency_id, rate, date) VALUES (?, ?, datetime('now'))",
+            "INSERT INTO currency_rate (currency_id, rate, date, source, fetched_at) VALUES (?, ?, datetime('now'), ?, datetime('now'))",
             currency_id,
-            rate
+            rate,
+            source
         )
         .execute(&self.pool)
         .await?
@@ -70,10 +72,109 @@ impl CurrencyDao {
         Ok(id)
     }
 
+    pub async fn add_rate_on(
+        &self,
+        currency_id: i64,
+        rate: Rate,
+        date: NaiveDateTime,
+        source: &str,
+    ) -> Result<i64, Error> {
+        let rate: i64 = rate.into();
+        let id = sqlx::query!(
+            "INSERT INTO currency_rate (currency_id, rate, date, source, fetched_at) VALUES (?, ?, ?, ?, datetime('now'))",
+            currency_id,
+            rate,
+            date,
+            source
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    pub async fn find_rate_on(
+        &self,
+        currency_id: i64,
+        date: NaiveDateTime,
+    ) -> Result<Option<CurrencyRate>, Error> {
+        let rate = sqlx::query_as!(
+            CurrencyRate,
+            "SELECT * FROM currency_rate WHERE currency_id = ? AND date = ? AND superseded_at IS NULL ORDER BY id DESC LIMIT 1",
+            currency_id,
+            date
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(rate)
+    }
+
+    pub async fn get_rate(&self, id: i64) -> Result<Option<CurrencyRate>, Error> {
+        let rate = sqlx::query_as!(CurrencyRate, "SELECT * FROM currency_rate WHERE id = ?", id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(rate)
+    }
+
+    /// Replaces the rate with a new version. The old row is kept for operations booked with it.
+    pub async fn correct_rate(
+        &self,
+        rate: &CurrencyRate,
+        value: Rate,
+        source: &str,
+    ) -> Result<i64, Error> {
+        let mut tx = self.pool.begin().await?;
+        let old_id = rate.id;
+        let superseded = sqlx::query!(
+            "UPDATE currency_rate SET superseded_at = datetime('now') WHERE id = ? AND superseded_at IS NULL",
+            old_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if superseded == 0 {
+            return Err(Error::msg("Rate is already corrected"));
+        }
+
+        let value: i64 = value.into();
+        let version = rate.version + 1;
+        let id = sqlx::query!(
+            "INSERT INTO currency_rate (currency_id, rate, date, source, fetched_at, version) VALUES (?, ?, ?, ?, datetime('now'), ?)",
+            rate.currency_id,
+            value,
+            rate.date,
+            source,
+            version
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        tx.commit().await?;
+        debug!("rate {} corrected by {}", old_id, id);
+        Ok(id)
+    }
+
+    /// All versions of the currency rate on the date, oldest first.
+    pub async fn get_rate_history(
+        &self,
+        currency_id: i64,
+        date: NaiveDateTime,
+    ) -> Result<Vec<CurrencyRate>, Error> {
+        let rates = sqlx::query_as!(
+            CurrencyRate,
+            "SELECT * FROM currency_rate WHERE currency_id = ? AND date = ? ORDER BY version",
+            currency_id,
+            date
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rates)
+    }
+
     pub async fn get_rates(&self, currency_id: i64) -> Result<Vec<CurrencyRate>, Error> {
         let rate = sqlx::query_as!(
             CurrencyRate,
-            "SELECT * FROM currency_rate WHERE currency_id = ?",
+            "SELECT * FROM currency_rate WHERE currency_id = ? AND superseded_at IS NULL",
             currency_id
         )
         .fetch_all(&self.pool)
@@ -84,7 +185,7 @@ impl CurrencyDao {
     pub async fn last_rate(&self, currency_id: i64) -> Result<CurrencyRate, Error> {
         let rate = sqlx::query_as!(
             CurrencyRate,
-            "SELECT * FROM currency_rate WHERE currency_id = ? ORDER BY date DESC LIMIT 1",
+            "SELECT * FROM currency_rate WHERE currency_id = ? AND superseded_at IS NULL ORDER BY date DESC LIMIT 1",
             currency_id
         )
         .fetch_one(&self.pool)
@@ -92,6 +193,23 @@ impl CurrencyDao {
         Ok(rate)
     }
 
+    /// Latest current rate dated at or before `date`.
+    pub async fn find_rate_at(
+        &self,
+        currency_id: i64,
+        date: NaiveDateTime,
+    ) -> Result<Option<CurrencyRate>, Error> {
+        let rate = sqlx::query_as!(
+            CurrencyRate,
+            "SELECT * FROM currency_rate WHERE currency_id = ? AND date <= ? AND superseded_at IS NULL ORDER BY date DESC, id DESC LIMIT 1",
+            currency_id,
+            date
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(rate)
+    }
+
     pub async fn get_rates_paging(
         &self,
         currency_id: i64,
@@ -101,7 +219,7 @@ impl CurrencyDao {
         let offset = page * size;
         let rate = sqlx::query_as!(
             CurrencyRate,
-            "SELECT * FROM currency_rate WHERE currency_id = ? LIMIT ? OFFSET ?",
+            "SELECT * FROM currency_rate WHERE currency_id = ? AND superseded_at IS NULL LIMIT ? OFFSET ?",
             currency_id,
             size,
             offset
@@ -111,6 +229,50 @@ impl CurrencyDao {
         Ok(rate)
     }
 
+    pub async fn count_rate_operations(&self, id: i64) -> Result<i64, Error> {
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) AS \"count: i64\" FROM asset_operations WHERE currency_rate = ?",
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    pub async fn referenced_rates(&self, currency_id: i64) -> Result<Vec<i64>, Error> {
+        let ids = sqlx::query_scalar!(
+            "SELECT DISTINCT asset_operations.currency_rate FROM asset_operations \
+            INNER JOIN currency_rate ON currency_rate.id = asset_operations.currency_rate \
+            WHERE currency_rate.currency_id = ?",
+            currency_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    /// Removes rates with their superseded versions. Rates used by operations are never removed.
+    /// Returns the number of removed rows.
+    pub async fn drop_unreferenced_rates(&self, rates: &[CurrencyRate]) -> Result<u64, Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut removed = 0;
+        for rate in rates {
+            removed += sqlx::query!(
+                "DELETE FROM currency_rate WHERE currency_id = ? AND date = ? \
+                AND (id = ? OR superseded_at IS NOT NULL) \
+                AND id NOT IN (SELECT currency_rate FROM asset_operations)",
+                rate.currency_id,
+                rate.date,
+                rate.id
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(removed)
+    }
+
     pub async fn drop_rate(&self, id: i64) -> Result<(), Error> {
         sqlx::query!("DELETE FROM currency_rate WHERE id = ?", id)
             .execute(&self.pool)
@@ -118,3 +280,102 @@ impl CurrencyDao {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::assets::AssetsDao;
+    use crate::dao::model::operations::OperationType;
+    use crate::dao::{test_pool, test_pool_before};
+    use crate::service::decimal::{dec, rate};
+    use crate::service::identifiers::AssetIdentifiers;
+    use crate::service::FinanceService;
+
+    #[tokio::test]
+    async fn test_correct_rate() {
+        let pool = test_pool().await;
+        let finance = FinanceService::new(pool.clone());
+        let dao = CurrencyDao::new(pool.clone());
+        let currency = finance.seed_currency("EUR", rate("1.1")).await.unwrap();
+        finance
+            .add_asset(
+                "SAP".to_string(),
+                None,
+                None,
+                "EUR".to_string(),
+                AssetIdentifiers::default(),
+            )
+            .await
+            .unwrap();
+        finance
+            .add_operation("SAP".to_string(), dec("100"), OperationType::UpdatePrice)
+            .await
+            .unwrap();
+        let old = dao.last_rate(currency.id).await.unwrap();
+
+        let id = dao.correct_rate(&old, rate("1.2"), "manual").await.unwrap();
+        let new = dao.get_rate(id).await.unwrap().unwrap();
+        assert_eq!(new.rate, rate("1.2"));
+        assert_eq!(new.date, old.date);
+        assert_eq!(new.version, old.version + 1);
+        assert_eq!(new.superseded_at, None);
+        let superseded = dao.get_rate(old.id).await.unwrap().unwrap();
+        assert!(superseded.superseded_at.is_some());
+        assert!(dao.correct_rate(&old, rate("1.3"), "manual").await.is_err());
+
+        let current = dao.get_rates(currency.id).await.unwrap();
+        assert_eq!(current.iter().map(|r| r.id).collect::<Vec<_>>(), vec![id]);
+        assert_eq!(dao.last_rate(currency.id).await.unwrap().id, id);
+        let on = dao.find_rate_on(currency.id, old.date).await.unwrap();
+        assert_eq!(on.map(|r| r.id), Some(id));
+        let history = dao.get_rate_history(currency.id, old.date).await.unwrap();
+        assert_eq!(
+            history.iter().map(|r| r.version).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let assets = AssetsDao::new(pool);
+        let asset = finance.find_asset("SAP").await.unwrap().unwrap();
+        let ops = assets.get_operations(&asset).await.unwrap();
+        assert_eq!(ops[0].currency_rate, old.id);
+        let rates = assets.get_operation_rates(&asset).await.unwrap();
+        assert_eq!(rates.len(), 1);
+        assert_eq!(rates[0].rate, rate("1.1"));
+    }
+
+    #[tokio::test]
+    async fn test_normalize_ticker_migration() {
+        let pool = test_pool_before(20231009110000).await;
+        // The first migration seeds 'usd' with id 1 and its rate.
+        for query in [
+            "INSERT INTO currency (id, ticker, name) VALUES (2, 'USD', 'US Dollar')",
+            "INSERT INTO currency_rate (id, currency_id, rate, date) VALUES (2, 2, 101, '2023-10-01 00:00:00')",
+            "INSERT INTO asset (id, name, ticker, description, currency) VALUES (1, 'Apple', 'AAPL', '', 1)",
+            "INSERT INTO asset (id, name, ticker, description, currency) VALUES (2, 'Tesla', 'TSLA', '', 2)",
+        ] {
+            sqlx::query(query).execute(&pool).await.unwrap();
+        }
+        sqlx::migrate!().run(&pool).await.unwrap();
+
+        let finance = FinanceService::new(pool.clone());
+        let base = finance.base_currency().await.unwrap();
+        assert_eq!(base.id, 1);
+        assert_eq!(base.ticker, "USD");
+        let dao = CurrencyDao::new(pool.clone());
+        assert_eq!(dao.list().await.unwrap().len(), 1);
+        let mut rates = dao
+            .get_rates(1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect::<Vec<_>>();
+        rates.sort();
+        assert_eq!(rates, vec![1, 2]);
+        let currencies: Vec<i64> = sqlx::query_scalar("SELECT currency FROM asset ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(currencies, vec![1, 1]);
+    }
+}
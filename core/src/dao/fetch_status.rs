@@ -0,0 +1,62 @@
+use crate::dao::model::fetch_status::FetchStatus;
+use color_eyre::eyre::Error;
+use sqlx::{Pool, Sqlite};
+
+pub struct FetchStatusDao {
+    pool: Pool<Sqlite>,
+}
+
+impl FetchStatusDao {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<FetchStatus>, Error> {
+        let status = sqlx::query_as!(FetchStatus, "SELECT * FROM fetch_status")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(status)
+    }
+
+    pub async fn get(&self, kind: &str, ticker: &str) -> Result<Option<FetchStatus>, Error> {
+        let status = sqlx::query_as!(
+            FetchStatus,
+            "SELECT * FROM fetch_status WHERE kind = ? AND ticker = ?",
+            kind,
+            ticker
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(status)
+    }
+
+    pub async fn record(
+        &self,
+        kind: &str,
+        ticker: &str,
+        provider: &str,
+        attempts: i64,
+        error: Option<String>,
+    ) -> Result<(), Error> {
+        let success = error.is_none();
+        sqlx::query!(
+            "INSERT INTO fetch_status (kind, ticker, provider, last_attempt, last_success, attempts, error) \
+            VALUES (?, ?, ?, datetime('now'), CASE WHEN ? THEN datetime('now') END, ?, ?) \
+            ON CONFLICT (kind, ticker) DO UPDATE SET \
+            provider = excluded.provider, \
+            last_attempt = excluded.last_attempt, \
+            last_success = COALESCE(excluded.last_success, fetch_status.last_success), \
+            attempts = excluded.attempts, \
+            error = excluded.error",
+            kind,
+            ticker,
+            provider,
+            success,
+            attempts,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
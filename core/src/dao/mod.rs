@@ -1,3 +1,46 @@
+pub mod account;
 pub mod assets;
+pub mod cash;
 pub mod currency;
+pub mod fetch_status;
 pub mod model;
+pub mod target;
+
+/// In-memory database with all migrations applied.
+#[cfg(test)]
+pub async fn test_pool() -> sqlx::Pool<sqlx::Sqlite> {
+    let pool = memory_pool().await;
+    sqlx::migrate!().run(&pool).await.unwrap();
+    pool
+}
+
+/// In-memory database with migrations older than `version` applied.
+/// The rest are applied by `sqlx::migrate!()` once the old schema is seeded.
+#[cfg(test)]
+pub async fn test_pool_before(version: i64) -> sqlx::Pool<sqlx::Sqlite> {
+    let pool = memory_pool().await;
+    let migrator = sqlx::migrate!();
+    let migrations = migrator
+        .migrations
+        .iter()
+        .filter(|m| m.version < version)
+        .cloned()
+        .collect::<Vec<_>>();
+    sqlx::migrate::Migrator {
+        migrations: migrations.into(),
+        ..migrator
+    }
+    .run(&pool)
+    .await
+    .unwrap();
+    pool
+}
+
+#[cfg(test)]
+async fn memory_pool() -> sqlx::Pool<sqlx::Sqlite> {
+    sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
@@ -0,0 +1,69 @@
+use color_eyre::eyre::Error;
+
+/// Name of the account created by migration. Operations without an account belong to it.
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+pub struct Account {
+    pub id: i64,
+    pub name: String,
+    pub broker: String,
+    /// Currency statistics are reported in.
+    pub currency: i64,
+    pub account_type: AccountType,
+}
+
+/// Raw `account` row.
+pub struct AccountRow {
+    pub id: i64,
+    pub name: String,
+    pub broker: String,
+    pub currency: i64,
+    pub account_type: String,
+}
+
+impl TryFrom<AccountRow> for Account {
+    type Error = Error;
+
+    fn try_from(row: AccountRow) -> Result<Self, Self::Error> {
+        let account_type = AccountType::try_from(row.account_type.as_str())
+            .map_err(|err| Error::msg(format!("Account {}: {}", row.name, err)))?;
+        Ok(Account {
+            id: row.id,
+            name: row.name,
+            broker: row.broker,
+            currency: row.currency,
+            account_type,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountType {
+    Brokerage,
+    /// Tax-advantaged retirement account, e.g. IRA.
+    Retirement,
+    Bank,
+}
+
+impl TryFrom<&str> for AccountType {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "Brokerage" => AccountType::Brokerage,
+            "Retirement" => AccountType::Retirement,
+            "Bank" => AccountType::Bank,
+            _ => return Err(Error::msg(format!("Unknown account type: {}", value))),
+        })
+    }
+}
+
+impl From<AccountType> for String {
+    fn from(val: AccountType) -> Self {
+        match val {
+            AccountType::Brokerage => "Brokerage".to_string(),
+            AccountType::Retirement => "Retirement".to_string(),
+            AccountType::Bank => "Bank".to_string(),
+        }
+    }
+}
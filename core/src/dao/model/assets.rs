@@ -1,18 +1,96 @@
+use crate::service::decimal::Decimal;
+use color_eyre::eyre::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AssetType {
     pub id: i64,
     pub name: String,
     pub description: String,
+    /// Parent category, `None` for a root.
+    pub parent: Option<i64>,
+    pub classification: i64,
+}
+
+pub const DEFAULT_CLASSIFICATION: &str = "default";
+
+/// Classification scheme with its own set of types, e.g. asset class or region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Classification {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
 }
 
+#[derive(Debug, Clone)]
 pub struct AssetToType {
     pub asset_id: i64,
-    pub type_id: String,
+    pub type_id: i64,
+    /// Share of the asset in the type, in percent.
+    pub weight: Decimal,
 }
 
+#[derive(Clone, Default)]
 pub struct Asset {
     pub id: i64,
     pub name: String,
     pub ticker: String,
     pub description: String,
     pub currency: i64,
+    /// Market identifier code, empty if unknown. Tickers are unique per exchange.
+    pub exchange: String,
+    pub isin: Option<String>,
+    pub figi: Option<String>,
+    pub cusip: Option<String>,
+    /// See [InstrumentClass].
+    pub instrument_class: String,
+    /// Trades are made in multiples of the lot size.
+    pub lot_size: Decimal,
+    /// Currency the value depends on if it differs from the trading one,
+    /// e.g. a gold ETF traded in RUB.
+    pub exposure_currency: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstrumentClass {
+    Equity,
+    Bond,
+    Fund,
+    Etf,
+    Commodity,
+    Crypto,
+    Cash,
+    Other,
+}
+
+impl TryFrom<&str> for InstrumentClass {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "Equity" => InstrumentClass::Equity,
+            "Bond" => InstrumentClass::Bond,
+            "Fund" => InstrumentClass::Fund,
+            "Etf" => InstrumentClass::Etf,
+            "Commodity" => InstrumentClass::Commodity,
+            "Crypto" => InstrumentClass::Crypto,
+            "Cash" => InstrumentClass::Cash,
+            "Other" => InstrumentClass::Other,
+            _ => return Err(Error::msg(format!("Unknown instrument class: {}", value))),
+        })
+    }
+}
+
+impl From<InstrumentClass> for String {
+    fn from(val: InstrumentClass) -> Self {
+        match val {
+            InstrumentClass::Equity => "Equity".to_string(),
+            InstrumentClass::Bond => "Bond".to_string(),
+            InstrumentClass::Fund => "Fund".to_string(),
+            InstrumentClass::Etf => "Etf".to_string(),
+            InstrumentClass::Commodity => "Commodity".to_string(),
+            InstrumentClass::Crypto => "Crypto".to_string(),
+            InstrumentClass::Cash => "Cash".to_string(),
+            InstrumentClass::Other => "Other".to_string(),
+        }
+    }
 }
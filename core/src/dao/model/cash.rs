@@ -0,0 +1,108 @@
+use crate::service::decimal::Decimal;
+use chrono::NaiveDateTime;
+use color_eyre::eyre::Error;
+
+/// Cash movement. Amount is positive for credit and negative for debit.
+pub struct CashOperation {
+    pub id: i64,
+    pub account: i64,
+    pub currency: i64,
+    pub operation_type: CashOperationType,
+    pub operation_date: NaiveDateTime,
+    pub amount: Decimal,
+    /// Asset operation which moved the cash.
+    pub asset_operation: Option<i64>,
+    /// Exchange the cash movement is a leg of.
+    pub exchange: Option<i64>,
+}
+
+/// Raw `cash_operations` row.
+pub struct CashOperationRow {
+    pub id: i64,
+    pub account: i64,
+    pub currency: i64,
+    pub operation_type: String,
+    pub operation_date: NaiveDateTime,
+    pub amount: Decimal,
+    pub asset_operation: Option<i64>,
+    pub exchange: Option<i64>,
+}
+
+impl TryFrom<CashOperationRow> for CashOperation {
+    type Error = Error;
+
+    fn try_from(row: CashOperationRow) -> Result<Self, Self::Error> {
+        let operation_type = CashOperationType::try_from(row.operation_type.as_str())
+            .map_err(|err| Error::msg(format!("Cash operation {}: {}", row.id, err)))?;
+        Ok(CashOperation {
+            id: row.id,
+            account: row.account,
+            currency: row.currency,
+            operation_type,
+            operation_date: row.operation_date,
+            amount: row.amount,
+            asset_operation: row.asset_operation,
+            exchange: row.exchange,
+        })
+    }
+}
+
+/// Conversion between cash balances of one account.
+pub struct CashExchange {
+    pub id: i64,
+    pub account: i64,
+    pub exchange_date: NaiveDateTime,
+    pub from_currency: i64,
+    pub from_amount: Decimal,
+    pub to_currency: i64,
+    pub to_amount: Decimal,
+    pub fee: Decimal,
+    pub fee_currency: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CashOperationType {
+    Deposit,
+    Withdrawal,
+    /// Trade, income or expense of an asset.
+    Asset,
+    /// Leg of a currency exchange.
+    Exchange,
+}
+
+impl TryFrom<&str> for CashOperationType {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "Deposit" => CashOperationType::Deposit,
+            "Withdrawal" => CashOperationType::Withdrawal,
+            "Asset" => CashOperationType::Asset,
+            "Exchange" => CashOperationType::Exchange,
+            _ => {
+                return Err(Error::msg(format!(
+                    "Unknown cash operation type: {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+impl From<CashOperationType> for String {
+    fn from(val: CashOperationType) -> Self {
+        match val {
+            CashOperationType::Deposit => "Deposit".to_string(),
+            CashOperationType::Withdrawal => "Withdrawal".to_string(),
+            CashOperationType::Asset => "Asset".to_string(),
+            CashOperationType::Exchange => "Exchange".to_string(),
+        }
+    }
+}
+
+/// Cash balance of one currency in the account.
+pub struct CashBalance {
+    pub account: i64,
+    pub currency: i64,
+    pub balance: Decimal,
+}
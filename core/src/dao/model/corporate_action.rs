@@ -0,0 +1,93 @@
+use crate::service::decimal::Decimal;
+use chrono::NaiveDateTime;
+use color_eyre::eyre::Error;
+
+/// Corporate action linking assets.
+pub struct CorporateAction {
+    pub id: i64,
+    pub action_type: CorporateActionType,
+    pub action_date: NaiveDateTime,
+    /// Asset the action applies to.
+    pub asset_id: i64,
+    /// Asset receiving the position, for merger and spin-off.
+    pub target_id: Option<i64>,
+    /// Target shares per source share. 1 for ticker change.
+    pub ratio: Decimal,
+    /// Share of the cost basis moved to the target. 1 for merger, 0 for ticker change.
+    pub cost_basis: Decimal,
+    /// Ticker before a ticker change.
+    pub old_ticker: Option<String>,
+    /// Name before a ticker change.
+    pub old_name: Option<String>,
+}
+
+/// Raw `corporate_action` row.
+pub struct CorporateActionRow {
+    pub id: i64,
+    pub action_type: String,
+    pub action_date: NaiveDateTime,
+    pub asset_id: i64,
+    pub target_id: Option<i64>,
+    pub ratio: Decimal,
+    pub cost_basis: Decimal,
+    pub old_ticker: Option<String>,
+    pub old_name: Option<String>,
+}
+
+impl TryFrom<CorporateActionRow> for CorporateAction {
+    type Error = Error;
+
+    fn try_from(row: CorporateActionRow) -> Result<Self, Self::Error> {
+        let action_type = CorporateActionType::try_from(row.action_type.as_str())
+            .map_err(|err| Error::msg(format!("Corporate action {}: {}", row.id, err)))?;
+        Ok(CorporateAction {
+            id: row.id,
+            action_type,
+            action_date: row.action_date,
+            asset_id: row.asset_id,
+            target_id: row.target_id,
+            ratio: row.ratio,
+            cost_basis: row.cost_basis,
+            old_ticker: row.old_ticker,
+            old_name: row.old_name,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorporateActionType {
+    /// Asset is renamed, history stays with the asset.
+    TickerChange,
+    /// Position is converted into the target asset with the cost basis.
+    Merger,
+    /// Target asset is received, part of the cost basis moves to it.
+    SpinOff,
+}
+
+impl TryFrom<&str> for CorporateActionType {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "TickerChange" => CorporateActionType::TickerChange,
+            "Merger" => CorporateActionType::Merger,
+            "SpinOff" => CorporateActionType::SpinOff,
+            _ => {
+                return Err(Error::msg(format!(
+                    "Unknown corporate action type: {}",
+                    value
+                )))
+            }
+        })
+    }
+}
+
+impl From<CorporateActionType> for String {
+    fn from(val: CorporateActionType) -> Self {
+        match val {
+            CorporateActionType::TickerChange => "TickerChange".to_string(),
+            CorporateActionType::Merger => "Merger".to_string(),
+            CorporateActionType::SpinOff => "SpinOff".to_string(),
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use crate::service::decimal::Decimal;
+use crate::service::decimal::Rate;
 use chrono::NaiveDateTime;
+use std::fmt::Display;
 
 pub struct Currency {
     pub id: i64,
@@ -10,6 +11,33 @@ pub struct Currency {
 pub struct CurrencyRate {
     pub id: i64,
     pub currency_id: i64,
-    pub rate: Decimal,
+    pub rate: Rate,
     pub date: NaiveDateTime,
+    /// Where the rate came from, see [RateSource].
+    pub source: String,
+    /// When the rate was written.
+    pub fetched_at: NaiveDateTime,
+    /// Correction number, starts from 1.
+    pub version: i64,
+    /// Set when the rate is replaced by a correction.
+    pub superseded_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateSource {
+    Manual,
+    /// Imported from file.
+    Import(String),
+    /// Fetched from provider.
+    Provider(String),
+}
+
+impl Display for RateSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateSource::Manual => write!(f, "manual"),
+            RateSource::Import(file) => write!(f, "import:{}", file),
+            RateSource::Provider(name) => write!(f, "provider:{}", name),
+        }
+    }
 }
@@ -0,0 +1,15 @@
+use chrono::NaiveDateTime;
+
+pub struct FetchStatus {
+    pub id: i64,
+    /// Instrument kind: `Currency` or `Asset`.
+    pub kind: String,
+    pub ticker: String,
+    pub provider: String,
+    pub last_attempt: NaiveDateTime,
+    pub last_success: Option<NaiveDateTime>,
+    /// Number of attempts made during the last fetch.
+    pub attempts: i64,
+    /// Error of the last fetch, if it failed.
+    pub error: Option<String>,
+}
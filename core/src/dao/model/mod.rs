@@ -1,3 +1,8 @@
+pub mod account;
 pub mod assets;
+pub mod cash;
+pub mod corporate_action;
 pub mod currency;
+pub mod fetch_status;
 pub mod operations;
+pub mod target;
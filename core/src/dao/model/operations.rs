@@ -1,6 +1,8 @@
 use crate::service::decimal::Decimal;
 use chrono::NaiveDateTime;
+use color_eyre::eyre::Error;
 
+#[derive(Clone)]
 pub struct AssetOperation {
     pub id: i64,
     pub asset_id: i64,
@@ -8,30 +10,94 @@ pub struct AssetOperation {
     pub operation_date: NaiveDateTime,
     pub operation_amount: Decimal,
     pub currency_rate: i64,
+    pub account: i64,
 }
 
+/// Raw `asset_operations` row.
+pub struct AssetOperationRow {
+    pub id: i64,
+    pub asset_id: i64,
+    pub operation_type: String,
+    pub operation_date: NaiveDateTime,
+    pub operation_amount: Decimal,
+    pub currency_rate: i64,
+    pub account: i64,
+}
+
+impl TryFrom<AssetOperationRow> for AssetOperation {
+    type Error = Error;
+
+    fn try_from(row: AssetOperationRow) -> Result<Self, Self::Error> {
+        let operation_type = OperationType::try_from(row.operation_type.as_str())
+            .map_err(|err| Error::msg(format!("Operation {}: {}", row.id, err)))?;
+        Ok(AssetOperation {
+            id: row.id,
+            asset_id: row.asset_id,
+            operation_type,
+            operation_date: row.operation_date,
+            operation_amount: row.operation_amount,
+            currency_rate: row.currency_rate,
+            account: row.account,
+        })
+    }
+}
+
+/// Operation type. Meaning of the operation amount depends on the type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperationType {
+    /// Amount is the new price.
     UpdatePrice,
+    /// Amount is quantity.
     Buy,
+    /// Amount is quantity.
     Sell,
+    /// Amount is money.
     Dividend,
+    /// Amount is the ratio, e.g. 2 for 2-for-1 split.
+    Split,
+    /// Amount is the ratio, e.g. 10 for 1-for-10 reverse split.
+    ReverseSplit,
+    /// Amount is money.
+    Fee,
+    /// Amount is money.
+    Tax,
+    /// Amount is money.
+    Interest,
+    /// Amount is money put into a cash-like asset (unit price is 1).
+    CashDeposit,
+    /// Amount is money taken from a cash-like asset (unit price is 1).
+    CashWithdrawal,
+    /// Amount is quantity bought with dividend.
+    DividendReinvestment,
 }
 
-impl From<String> for OperationType {
-    fn from(value: String) -> Self {
-        From::from(value.as_str())
+impl TryFrom<String> for OperationType {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        TryFrom::try_from(value.as_str())
     }
 }
 
-impl From<&str> for OperationType {
-    fn from(value: &str) -> Self {
-        match value {
+impl TryFrom<&str> for OperationType {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match value {
             "UpdatePrice" => OperationType::UpdatePrice,
             "Buy" => OperationType::Buy,
             "Sell" => OperationType::Sell,
             "Dividend" => OperationType::Dividend,
-            _ => panic!("Unknown operation type"),
-        }
+            "Split" => OperationType::Split,
+            "ReverseSplit" => OperationType::ReverseSplit,
+            "Fee" => OperationType::Fee,
+            "Tax" => OperationType::Tax,
+            "Interest" => OperationType::Interest,
+            "CashDeposit" => OperationType::CashDeposit,
+            "CashWithdrawal" => OperationType::CashWithdrawal,
+            "DividendReinvestment" => OperationType::DividendReinvestment,
+            _ => return Err(Error::msg(format!("Unknown operation type: {}", value))),
+        })
     }
 }
 
@@ -42,6 +108,14 @@ impl From<OperationType> for String {
             OperationType::Buy => "Buy".to_string(),
             OperationType::Sell => "Sell".to_string(),
             OperationType::Dividend => "Dividend".to_string(),
+            OperationType::Split => "Split".to_string(),
+            OperationType::ReverseSplit => "ReverseSplit".to_string(),
+            OperationType::Fee => "Fee".to_string(),
+            OperationType::Tax => "Tax".to_string(),
+            OperationType::Interest => "Interest".to_string(),
+            OperationType::CashDeposit => "CashDeposit".to_string(),
+            OperationType::CashWithdrawal => "CashWithdrawal".to_string(),
+            OperationType::DividendReinvestment => "DividendReinvestment".to_string(),
         }
     }
 }
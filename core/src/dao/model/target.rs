@@ -0,0 +1,14 @@
+use crate::service::decimal::Decimal;
+
+/// Drift band used when none is set, in percentage points.
+pub const DEFAULT_DRIFT_BAND: i64 = 5;
+
+/// Target share of an asset type in the portfolio.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetAllocation {
+    pub type_id: i64,
+    /// Target share, in percent.
+    pub weight: Decimal,
+    /// Allowed absolute drift, in percentage points.
+    pub band: Decimal,
+}
@@ -0,0 +1,47 @@
+use crate::dao::model::assets::AssetType;
+use crate::dao::model::target::TargetAllocation;
+use crate::service::decimal::Decimal;
+use color_eyre::eyre::Error;
+use sqlx::{Pool, Sqlite};
+
+#[derive(Clone)]
+pub struct TargetDao {
+    pool: Pool<Sqlite>,
+}
+
+impl TargetDao {
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<TargetAllocation>, Error> {
+        let targets = sqlx::query_as!(TargetAllocation, "SELECT * FROM target_allocation")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(targets)
+    }
+
+    pub async fn set(&self, tp: &AssetType, weight: Decimal, band: Decimal) -> Result<(), Error> {
+        let type_id = tp.id;
+        let weight: i64 = weight.into();
+        let band: i64 = band.into();
+        sqlx::query!(
+            "INSERT INTO target_allocation (type_id, weight, band) VALUES (?, ?, ?) \
+            ON CONFLICT (type_id) DO UPDATE SET weight = excluded.weight, band = excluded.band",
+            type_id,
+            weight,
+            band
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, tp: &AssetType) -> Result<(), Error> {
+        let type_id = tp.id;
+        sqlx::query!("DELETE FROM target_allocation WHERE type_id = ?", type_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use crate::dao::account::AccountDao;
+use crate::dao::model::account::{Account, AccountType, DEFAULT_ACCOUNT};
+use crate::dao::model::currency::Currency;
+use color_eyre::eyre::Error;
+
+pub struct AccountService {
+    dao: AccountDao,
+}
+
+impl AccountService {
+    pub fn new(dao: AccountDao) -> Self {
+        Self { dao }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Account>, Error> {
+        self.dao.list().await
+    }
+
+    pub async fn account(&self, name: &str) -> Result<Option<Account>, Error> {
+        self.dao.get_by_name(name).await
+    }
+
+    pub async fn default_account(&self) -> Result<Account, Error> {
+        self.account(DEFAULT_ACCOUNT)
+            .await?
+            .ok_or(Error::msg("Default account not found"))
+    }
+
+    pub async fn create(
+        &self,
+        name: String,
+        broker: String,
+        currency: &Currency,
+        account_type: AccountType,
+    ) -> Result<Account, Error> {
+        if self.account(&name).await?.is_some() {
+            return Err(Error::msg("Account already exists"));
+        }
+        self.dao.add(name, broker, currency, account_type).await
+    }
+
+    pub async fn remove(&self, account: &Account) -> Result<(), Error> {
+        if account.name == DEFAULT_ACCOUNT {
+            return Err(Error::msg("Default account can't be removed"));
+        }
+        if self.dao.count_operations(account).await? > 0 {
+            return Err(Error::msg("Account has operations"));
+        }
+        self.dao.remove(account).await
+    }
+}
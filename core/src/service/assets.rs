@@ -1,9 +1,20 @@
 use crate::dao::assets::AssetsDao;
-use crate::dao::model::assets::{Asset, AssetType};
+use crate::dao::model::account::Account;
+use crate::dao::model::assets::{
+    Asset, AssetToType, AssetType, Classification, DEFAULT_CLASSIFICATION,
+};
+use crate::dao::model::corporate_action::{CorporateAction, CorporateActionType};
 use crate::dao::model::currency::{Currency, CurrencyRate};
-use crate::dao::model::operations::{AssetOperation, OperationType};
+use crate::dao::model::operations::AssetOperation;
+use crate::service::category::{
+    build_tree, check_weights, subtree, ChildTypes, ClassificationTree,
+};
 use crate::service::decimal::Decimal;
+use crate::service::identifiers::{AssetIdentifiers, AssetKey};
+use crate::service::statistics::cash::cash_flow;
+use chrono::NaiveDateTime;
 use color_eyre::eyre::Error;
+use std::str::FromStr;
 
 pub struct AssetsService {
     dao: AssetsDao,
@@ -29,27 +40,204 @@ impl AssetsService {
         self.dao.get_types().await
     }
 
-    pub async fn add_type(&self, name: String, description: String) -> Result<AssetType, Error> {
-        self.dao.add_type(name, description).await
+    /// Adds the type to the classification. The parent must be in the same classification.
+    pub async fn add_type(
+        &self,
+        name: String,
+        description: String,
+        parent: Option<&AssetType>,
+        classification: &Classification,
+    ) -> Result<AssetType, Error> {
+        check_name(&name)?;
+        if parent.is_some_and(|p| p.classification != classification.id) {
+            return Err(Error::msg("Parent type is in another classification"));
+        }
+        if self
+            .dao
+            .find_types_by_name(&name)
+            .await?
+            .iter()
+            .any(|t| t.classification == classification.id)
+        {
+            return Err(Error::msg("Type already exists"));
+        }
+        self.dao
+            .add_type(name, description, parent.map(|p| p.id), classification)
+            .await
+    }
+
+    pub async fn set_type_parent(
+        &self,
+        tp: &AssetType,
+        parent: Option<&AssetType>,
+    ) -> Result<(), Error> {
+        if let Some(parent) = parent {
+            if parent.classification != tp.classification {
+                return Err(Error::msg("Parent type is in another classification"));
+            }
+            let types = self.get_types().await?;
+            if subtree(&types, tp.id).contains(&parent.id) {
+                return Err(Error::msg("Type can't be moved under its own descendant"));
+            }
+        }
+        self.dao.set_type_parent(tp, parent.map(|p| p.id)).await
+    }
+
+    /// Type tree of every classification.
+    pub async fn type_trees(&self) -> Result<Vec<ClassificationTree>, Error> {
+        let types = self.get_types().await?;
+        Ok(self
+            .classifications()
+            .await?
+            .into_iter()
+            .map(|classification| {
+                let types = types
+                    .iter()
+                    .filter(|t| t.classification == classification.id)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                ClassificationTree {
+                    classification,
+                    types: build_tree(&types),
+                }
+            })
+            .collect())
+    }
+
+    pub async fn classifications(&self) -> Result<Vec<Classification>, Error> {
+        self.dao.get_classifications().await
+    }
+
+    pub async fn classification(&self, name: &str) -> Result<Option<Classification>, Error> {
+        self.dao.get_classification_by_name(name).await
+    }
+
+    pub async fn default_classification(&self) -> Result<Classification, Error> {
+        self.classification(DEFAULT_CLASSIFICATION)
+            .await?
+            .ok_or(Error::msg("Default classification not found"))
+    }
+
+    pub async fn add_classification(
+        &self,
+        name: String,
+        description: String,
+    ) -> Result<Classification, Error> {
+        check_name(&name)?;
+        if self.classification(&name).await?.is_some() {
+            return Err(Error::msg("Classification already exists"));
+        }
+        self.dao.add_classification(name, description).await
+    }
+
+    pub async fn remove_classification(
+        &self,
+        classification: &Classification,
+    ) -> Result<(), Error> {
+        if classification.name == DEFAULT_CLASSIFICATION {
+            return Err(Error::msg("Default classification can't be removed"));
+        }
+        if self
+            .get_types()
+            .await?
+            .iter()
+            .any(|t| t.classification == classification.id)
+        {
+            return Err(Error::msg("Classification has types"));
+        }
+        self.dao.remove_classification(classification).await
     }
 
-    pub async fn get_type_by_name(&self, name: String) -> Result<Option<AssetType>, Error> {
-        self.dao.get_type_by_name(name).await
+    pub async fn get_type_links(&self) -> Result<Vec<AssetToType>, Error> {
+        self.dao.get_type_links().await
     }
 
-    pub async fn get_asset_by_ticker(&self, ticker: String) -> Result<Option<Asset>, Error> {
-        self.dao.get_asset_by_ticker(ticker).await
+    /// Finds a type by `name` or `classification:name`.
+    pub async fn find_type(&self, key: &str) -> Result<Option<AssetType>, Error> {
+        let (classification, name) = match key.split_once(':') {
+            Some((classification, name)) => {
+                let classification = self
+                    .classification(classification.trim())
+                    .await?
+                    .ok_or(Error::msg("Classification not found"))?;
+                (Some(classification), name.trim())
+            }
+            None => (None, key.trim()),
+        };
+        let mut types = self.dao.find_types_by_name(name).await?;
+        if let Some(classification) = classification {
+            types.retain(|t| t.classification == classification.id);
+        }
+        if types.len() > 1 {
+            return Err(Error::msg(format!(
+                "{} exists in several classifications, use <classification>:{}",
+                name, name
+            )));
+        }
+        Ok(types.pop())
+    }
+
+    /// Finds an asset by any identifier, see [AssetKey].
+    pub async fn find_asset(&self, key: &str) -> Result<Option<Asset>, Error> {
+        match AssetKey::from_str(key)? {
+            AssetKey::Ticker(ticker) => {
+                let mut assets = self.dao.find_assets_by_ticker(&ticker).await?;
+                if assets.len() > 1 {
+                    return Err(Error::msg(format!(
+                        "{} is listed on several exchanges, use {}@<MIC>",
+                        ticker, ticker
+                    )));
+                }
+                Ok(assets.pop())
+            }
+            AssetKey::Listing { ticker, exchange } => {
+                self.dao.get_asset_by_listing(&ticker, &exchange).await
+            }
+            AssetKey::Isin(isin) => self.dao.get_asset_by_isin(&isin).await,
+            AssetKey::Figi(figi) => self.dao.get_asset_by_figi(&figi).await,
+            AssetKey::Cusip(cusip) => {
+                let mut assets = self.dao.find_assets_by_cusip(&cusip).await?;
+                if assets.len() > 1 {
+                    return Err(Error::msg(format!("CUSIP {} is ambiguous", cusip)));
+                }
+                Ok(assets.pop())
+            }
+        }
+    }
+
+    pub async fn find_assets_by_ticker(&self, ticker: &str) -> Result<Vec<Asset>, Error> {
+        self.dao.find_assets_by_ticker(ticker).await
     }
 
     pub async fn get_type(&self, id: i64) -> Result<Option<AssetType>, Error> {
         self.dao.get_type(id).await
     }
 
-    pub async fn remove_type(&self, tp: AssetType) -> Result<(), Error> {
-        if !self.find_assets_with_type(&tp).await?.is_empty() {
-            return Err(Error::msg("Type has assets"));
+    pub async fn remove_type(&self, tp: AssetType, children: ChildTypes) -> Result<(), Error> {
+        let has_children = self
+            .get_types()
+            .await?
+            .iter()
+            .any(|t| t.parent == Some(tp.id));
+        match children {
+            ChildTypes::Reject if has_children => Err(Error::msg("Type has child types")),
+            ChildTypes::Remove => {
+                if !self.find_assets_with_type(&tp).await?.is_empty() {
+                    return Err(Error::msg("Type or its descendants have assets"));
+                }
+                self.dao.remove_type_subtree(&tp).await
+            }
+            _ => {
+                if self.dao.count_type_assets(&tp).await? > 0 {
+                    return Err(Error::msg("Type has assets"));
+                }
+                if has_children {
+                    self.dao.remove_type_lifting_children(&tp).await
+                } else {
+                    self.dao.remove_type(&tp).await
+                }
+            }
         }
-        self.dao.remove_type(&tp).await
     }
 
     pub async fn get_assets(&self) -> Result<Vec<Asset>, Error> {
@@ -66,43 +254,270 @@ impl AssetsService {
         name: String,
         description: String,
         currency: &Currency,
+        ids: AssetIdentifiers,
     ) -> Result<Asset, Error> {
+        check_ticker(&ticker)?;
+        let ids = ids.normalize()?;
+        if self
+            .dao
+            .get_asset_by_listing(&ticker, &ids.exchange)
+            .await?
+            .is_some()
+        {
+            return Err(Error::msg("Asset with this ticker already exists"));
+        }
         self.dao
-            .add_asset(ticker, name, description, currency)
+            .add_asset(ticker, name, description, currency, ids)
             .await
     }
 
-    pub async fn add_asset_type(&self, asset: &Asset, tp: &AssetType) -> Result<(), Error> {
-        self.dao.add_asset_type(asset, tp).await
+    pub async fn set_identifiers(
+        &self,
+        asset: &Asset,
+        ids: AssetIdentifiers,
+    ) -> Result<Asset, Error> {
+        let ids = ids.normalize()?;
+        if ids.exchange != asset.exchange
+            && self
+                .dao
+                .get_asset_by_listing(&asset.ticker, &ids.exchange)
+                .await?
+                .is_some()
+        {
+            return Err(Error::msg("Asset with this ticker already exists"));
+        }
+        self.dao.set_identifiers(asset, ids).await
+    }
+
+    pub async fn set_lot_size(&self, asset: &Asset, lot_size: Decimal) -> Result<(), Error> {
+        if lot_size <= Decimal::default() {
+            return Err(Error::msg("Lot size must be positive"));
+        }
+        self.dao.set_lot_size(asset, lot_size).await
+    }
+
+    /// Sets the currency the asset value depends on, `None` to use the trading currency.
+    pub async fn set_exposure_currency(
+        &self,
+        asset: &Asset,
+        currency: Option<&Currency>,
+    ) -> Result<(), Error> {
+        self.dao.set_exposure_currency(asset, currency).await
+    }
+
+    /// Links the asset to the type with the weight in percent.
+    /// Weights of the asset within a dimension may not exceed 100%.
+    pub async fn add_asset_type(
+        &self,
+        asset: &Asset,
+        tp: &AssetType,
+        weight: Decimal,
+    ) -> Result<(), Error> {
+        let types = self.get_types().await?;
+        let mut links = self.dao.get_asset_links(asset).await?;
+        links.retain(|link| link.type_id != tp.id);
+        links.push(AssetToType {
+            asset_id: asset.id,
+            type_id: tp.id,
+            weight,
+        });
+        check_weights(&types, &links)?;
+        self.dao.add_asset_type(asset, tp, weight).await
+    }
+
+    /// Replaces the asset links within the dimensions covered by the composition.
+    /// Links in other dimensions are kept.
+    pub async fn set_composition(
+        &self,
+        asset: &Asset,
+        composition: &[(AssetType, Decimal)],
+    ) -> Result<(), Error> {
+        let types = self.get_types().await?;
+        let classifications = composition
+            .iter()
+            .map(|(tp, _)| tp.classification)
+            .collect::<Vec<_>>();
+        let (removed, mut links): (Vec<_>, Vec<_>) = self
+            .dao
+            .get_asset_links(asset)
+            .await?
+            .into_iter()
+            .partition(|link| {
+                types
+                    .iter()
+                    .any(|t| t.id == link.type_id && classifications.contains(&t.classification))
+            });
+        links.extend(composition.iter().map(|(tp, weight)| AssetToType {
+            asset_id: asset.id,
+            type_id: tp.id,
+            weight: *weight,
+        }));
+        check_weights(&types, &links)?;
+
+        let removed = removed.iter().map(|link| link.type_id).collect::<Vec<_>>();
+        let added = composition
+            .iter()
+            .map(|(tp, weight)| (tp.id, *weight))
+            .collect::<Vec<_>>();
+        self.dao.replace_asset_types(asset, &removed, &added).await
     }
 
     pub async fn remove_asset_type(&self, asset: &Asset, tp: &AssetType) -> Result<(), Error> {
         self.dao.remove_asset_type(asset, tp).await
     }
 
-    pub async fn remove_asset(&self, ticker: String) -> Result<(), Error> {
+    pub async fn remove_asset(&self, key: &str) -> Result<(), Error> {
         let asset = self
-            .get_asset_by_ticker(ticker.clone())
+            .find_asset(key)
             .await?
             .ok_or(Error::msg("Asset not found"))?;
         self.dao.remove_asset(&asset).await
     }
 
-    pub async fn add_operation(
+    /// Adds the operations in one transaction, moving account cash at the given asset prices.
+    pub async fn add_operations(
         &self,
-        asset: &Asset,
-        rate: &CurrencyRate,
-        tp: OperationType,
-        amount: Decimal,
+        operations: &[(AssetOperation, Decimal)],
     ) -> Result<(), Error> {
-        if rate.currency_id != asset.currency {
-            return Err(Error::msg("Currency mismatch"));
-        }
-
-        self.dao.add_operation(asset, rate, tp, amount).await
+        let operations = operations
+            .iter()
+            .map(|(op, price)| {
+                let cash = cash_flow(op.operation_type, op.operation_amount, *price);
+                (op.clone(), cash)
+            })
+            .collect::<Vec<_>>();
+        self.dao.add_operations(&operations).await?;
+        Ok(())
     }
 
     pub async fn get_operations(&self, asset: &Asset) -> Result<Vec<AssetOperation>, Error> {
         self.dao.get_operations(asset).await
     }
+
+    /// Operations of the asset, only the account ones if it is set.
+    pub async fn get_account_operations(
+        &self,
+        asset: &Asset,
+        account: Option<&Account>,
+    ) -> Result<Vec<AssetOperation>, Error> {
+        match account {
+            Some(account) => self.dao.get_account_operations(asset, account).await,
+            None => self.dao.get_operations(asset).await,
+        }
+    }
+
+    pub async fn get_operation_rates(&self, asset: &Asset) -> Result<Vec<CurrencyRate>, Error> {
+        self.dao.get_operation_rates(asset).await
+    }
+
+    pub async fn find_assets_in_account(&self, account: &Account) -> Result<Vec<Asset>, Error> {
+        self.dao.find_assets_in_account(account).await
+    }
+
+    pub async fn get_asset(&self, id: i64) -> Result<Option<Asset>, Error> {
+        self.dao.get_asset(id).await
+    }
+
+    pub async fn change_ticker(
+        &self,
+        asset: &Asset,
+        ticker: String,
+        name: String,
+        date: NaiveDateTime,
+    ) -> Result<Asset, Error> {
+        check_ticker(&ticker)?;
+        if ticker != asset.ticker
+            && self
+                .dao
+                .get_asset_by_listing(&ticker, &asset.exchange)
+                .await?
+                .is_some()
+        {
+            return Err(Error::msg("Asset with this ticker already exists"));
+        }
+        self.dao.change_ticker(asset, ticker, name, date).await
+    }
+
+    pub async fn merge(
+        &self,
+        asset: &Asset,
+        target: &Asset,
+        ratio: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<i64, Error> {
+        self.add_corporate_action(
+            CorporateActionType::Merger,
+            date,
+            asset,
+            target,
+            ratio,
+            Decimal::int(1),
+        )
+        .await
+    }
+
+    pub async fn spin_off(
+        &self,
+        asset: &Asset,
+        target: &Asset,
+        ratio: Decimal,
+        cost_basis: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<i64, Error> {
+        if cost_basis < Decimal::default() || cost_basis > Decimal::int(1) {
+            return Err(Error::msg("Cost basis share must be between 0 and 1"));
+        }
+        self.add_corporate_action(
+            CorporateActionType::SpinOff,
+            date,
+            asset,
+            target,
+            ratio,
+            cost_basis,
+        )
+        .await
+    }
+
+    async fn add_corporate_action(
+        &self,
+        action_type: CorporateActionType,
+        date: NaiveDateTime,
+        asset: &Asset,
+        target: &Asset,
+        ratio: Decimal,
+        cost_basis: Decimal,
+    ) -> Result<i64, Error> {
+        if asset.id == target.id {
+            return Err(Error::msg("Asset can't be linked to itself"));
+        }
+        if ratio <= Decimal::default() {
+            return Err(Error::msg("Ratio must be positive"));
+        }
+        self.dao
+            .add_corporate_action(action_type, date, asset, target, ratio, cost_basis)
+            .await
+    }
+
+    pub async fn get_corporate_actions(
+        &self,
+        asset: &Asset,
+    ) -> Result<Vec<CorporateAction>, Error> {
+        self.dao.get_corporate_actions(asset).await
+    }
+}
+
+/// Tickers must not look like other identifiers, see [AssetKey].
+fn check_ticker(ticker: &str) -> Result<(), Error> {
+    if ticker.trim().is_empty() || ticker.contains(['@', ':']) {
+        return Err(Error::msg(format!("Invalid ticker: {}", ticker)));
+    }
+    Ok(())
+}
+
+/// Type and classification names can't contain `:`, it separates them in type keys.
+fn check_name(name: &str) -> Result<(), Error> {
+    if name.trim().is_empty() || name.contains(':') {
+        return Err(Error::msg(format!("Invalid name: {}", name)));
+    }
+    Ok(())
 }
@@ -0,0 +1,90 @@
+use crate::dao::cash::CashDao;
+use crate::dao::model::account::Account;
+use crate::dao::model::cash::{CashBalance, CashExchange, CashOperation, CashOperationType};
+use crate::dao::model::currency::Currency;
+use crate::service::decimal::Decimal;
+use chrono::NaiveDateTime;
+use color_eyre::eyre::Error;
+
+pub struct CashService {
+    dao: CashDao,
+}
+
+impl CashService {
+    pub fn new(dao: CashDao) -> Self {
+        Self { dao }
+    }
+
+    pub async fn deposit(
+        &self,
+        account: &Account,
+        currency: &Currency,
+        amount: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<i64, Error> {
+        if amount <= Decimal::default() {
+            return Err(Error::msg("Amount must be positive"));
+        }
+        self.dao
+            .add_operation(account, currency, CashOperationType::Deposit, date, amount)
+            .await
+    }
+
+    pub async fn withdraw(
+        &self,
+        account: &Account,
+        currency: &Currency,
+        amount: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<i64, Error> {
+        if amount <= Decimal::default() {
+            return Err(Error::msg("Amount must be positive"));
+        }
+        self.dao
+            .add_operation(
+                account,
+                currency,
+                CashOperationType::Withdrawal,
+                date,
+                Decimal::default() - amount,
+            )
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn exchange(
+        &self,
+        account: &Account,
+        date: NaiveDateTime,
+        from: &Currency,
+        from_amount: Decimal,
+        to: &Currency,
+        to_amount: Decimal,
+        fee: Option<(Decimal, &Currency)>,
+    ) -> Result<CashExchange, Error> {
+        if from.id == to.id {
+            return Err(Error::msg("Exchange currencies must differ"));
+        }
+        if from_amount <= Decimal::default() || to_amount <= Decimal::default() {
+            return Err(Error::msg("Amount must be positive"));
+        }
+        if fee.is_some_and(|(fee, _)| fee < Decimal::default()) {
+            return Err(Error::msg("Fee must not be negative"));
+        }
+        self.dao
+            .add_exchange(account, date, from, from_amount, to, to_amount, fee)
+            .await
+    }
+
+    pub async fn exchanges(&self, account: &Account) -> Result<Vec<CashExchange>, Error> {
+        self.dao.get_exchanges(account).await
+    }
+
+    pub async fn operations(&self, account: &Account) -> Result<Vec<CashOperation>, Error> {
+        self.dao.get_operations(account).await
+    }
+
+    pub async fn balances(&self) -> Result<Vec<CashBalance>, Error> {
+        self.dao.balances().await
+    }
+}
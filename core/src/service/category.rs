@@ -0,0 +1,181 @@
+use crate::dao::model::assets::{AssetToType, AssetType, Classification};
+use crate::service::decimal::Decimal;
+use color_eyre::eyre::Error;
+use std::collections::HashMap;
+
+/// What to do with child types of a removed type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildTypes {
+    /// Refuse to remove a type with children.
+    Reject,
+    /// Move children to the parent of the removed type.
+    MoveUp,
+    /// Remove the whole subtree. Fails if any type in it has assets.
+    Remove,
+}
+
+/// Asset type with its child types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeNode {
+    pub tp: AssetType,
+    pub children: Vec<TypeNode>,
+}
+
+/// Types of one classification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassificationTree {
+    pub classification: Classification,
+    pub types: Vec<TypeNode>,
+}
+
+/// Builds the type forest. Types with an unknown parent become roots.
+pub fn build_tree(types: &[AssetType]) -> Vec<TypeNode> {
+    let ids = types.iter().map(|t| t.id).collect::<Vec<_>>();
+    let mut children = HashMap::<Option<i64>, Vec<&AssetType>>::new();
+    for tp in types {
+        let parent = tp.parent.filter(|p| ids.contains(p));
+        children.entry(parent).or_default().push(tp);
+    }
+
+    fn build(tp: &AssetType, children: &HashMap<Option<i64>, Vec<&AssetType>>) -> TypeNode {
+        TypeNode {
+            tp: tp.clone(),
+            children: children
+                .get(&Some(tp.id))
+                .map(|c| c.iter().map(|c| build(c, children)).collect())
+                .unwrap_or_default(),
+        }
+    }
+    children
+        .get(&None)
+        .map(|roots| roots.iter().map(|r| build(r, &children)).collect())
+        .unwrap_or_default()
+}
+
+/// Ids of the type and all of its descendants.
+pub fn subtree(types: &[AssetType], id: i64) -> Vec<i64> {
+    let mut ids = vec![id];
+    let mut idx = 0;
+    while idx < ids.len() {
+        let parent = ids[idx];
+        for tp in types {
+            if tp.parent == Some(parent) && !ids.contains(&tp.id) {
+                ids.push(tp.id);
+            }
+        }
+        idx += 1;
+    }
+    ids
+}
+
+/// Ids of the type ancestors from the parent up to the root.
+pub fn ancestors(types: &[AssetType], id: i64) -> Vec<i64> {
+    let parents = types
+        .iter()
+        .map(|t| (t.id, t.parent))
+        .collect::<HashMap<_, _>>();
+    let mut ids = Vec::new();
+    let mut current = parents.get(&id).copied().flatten();
+    while let Some(parent) = current {
+        if parent == id || ids.contains(&parent) {
+            break;
+        }
+        ids.push(parent);
+        current = parents.get(&parent).copied().flatten();
+    }
+    ids
+}
+
+/// Checks the link weights of one asset: each weight is in `(0, 100]`
+/// and the weights within a classification sum to at most 100%.
+pub fn check_weights(types: &[AssetType], links: &[AssetToType]) -> Result<(), Error> {
+    let hundred = Decimal::int(100);
+    let classifications = types
+        .iter()
+        .map(|t| (t.id, t.classification))
+        .collect::<HashMap<_, _>>();
+    let mut weights = HashMap::<Option<i64>, Decimal>::new();
+    for link in links {
+        if link.weight <= Decimal::default() || link.weight > hundred {
+            return Err(Error::msg(format!(
+                "Weight must be in (0, 100]: {}",
+                link.weight
+            )));
+        }
+        *weights
+            .entry(classifications.get(&link.type_id).copied())
+            .or_default() += link.weight;
+    }
+    if let Some(weight) = weights.into_values().find(|w| *w > hundred) {
+        return Err(Error::msg(format!(
+            "Weights within a classification sum to {}%",
+            weight
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+
+    fn tp(id: i64, name: &str, parent: Option<i64>) -> AssetType {
+        AssetType {
+            id,
+            name: name.to_string(),
+            description: "".to_string(),
+            parent,
+            classification: 1,
+        }
+    }
+
+    fn types() -> Vec<AssetType> {
+        vec![
+            tp(1, "Equity", None),
+            tp(2, "International", Some(1)),
+            tp(3, "Emerging Markets", Some(2)),
+            tp(4, "Domestic", Some(1)),
+            AssetType {
+                classification: 2,
+                ..tp(5, "USA", None)
+            },
+        ]
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let tree = build_tree(&types());
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].tp.name, "Equity");
+        assert_eq!(tree[0].children.len(), 2);
+        assert_eq!(tree[0].children[0].children[0].tp.name, "Emerging Markets");
+        assert!(tree[1].children.is_empty());
+    }
+
+    #[test]
+    fn test_subtree_and_ancestors() {
+        let types = types();
+        assert_eq!(subtree(&types, 1), vec![1, 2, 4, 3]);
+        assert_eq!(subtree(&types, 3), vec![3]);
+        assert_eq!(ancestors(&types, 3), vec![2, 1]);
+        assert!(ancestors(&types, 5).is_empty());
+    }
+
+    fn link(type_id: i64, weight: &str) -> AssetToType {
+        AssetToType {
+            asset_id: 1,
+            type_id,
+            weight: dec(weight),
+        }
+    }
+
+    #[test]
+    fn test_check_weights() {
+        let types = types();
+        assert!(check_weights(&types, &[link(3, "60"), link(5, "100")]).is_ok());
+        assert!(check_weights(&types, &[link(3, "60"), link(4, "40")]).is_ok());
+        assert!(check_weights(&types, &[link(3, "60"), link(4, "40.01")]).is_err());
+        assert!(check_weights(&types, &[link(5, "0")]).is_err());
+    }
+}
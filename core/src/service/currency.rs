@@ -1,6 +1,8 @@
 use crate::dao::currency::CurrencyDao;
-use crate::dao::model::currency::{Currency, CurrencyRate};
-use crate::service::decimal::Decimal;
+use crate::dao::model::currency::{Currency, CurrencyRate, RateSource};
+use crate::service::decimal::Rate;
+use crate::service::iso4217;
+use chrono::NaiveDateTime;
 use color_eyre::eyre::Error;
 
 pub struct CurrencyService {
@@ -38,8 +40,14 @@ impl CurrencyService {
         Ok(Some(rate))
     }
 
-    pub async fn currency(&self, ticker: &String) -> Result<Option<Currency>, Error> {
-        self.dao.find_by_ticker(ticker).await
+    pub async fn list(&self) -> Result<Vec<Currency>, Error> {
+        self.dao.list().await
+    }
+
+    pub async fn currency(&self, ticker: &str) -> Result<Option<Currency>, Error> {
+        self.dao
+            .find_by_ticker(&iso4217::normalize_ticker(ticker))
+            .await
     }
 
     pub async fn currency_by_id(&self, id: i64) -> Result<Option<Currency>, Error> {
@@ -65,12 +73,133 @@ impl CurrencyService {
     //     Ok(result)
     // }
 
-    pub async fn create(&self, name: String, ticker: String) -> Result<Currency, Error> {
+    /// Creates currency with normalized ticker.
+    /// Tickers outside of ISO 4217 (crypto, commodities) are accepted only if `custom` is set.
+    pub async fn create(
+        &self,
+        name: String,
+        ticker: String,
+        custom: bool,
+    ) -> Result<Currency, Error> {
+        let ticker = iso4217::normalize_ticker(&ticker);
+        if iso4217::find(&ticker).is_none() {
+            if !custom {
+                return Err(Error::msg(format!(
+                    "{} is not an ISO 4217 currency",
+                    ticker
+                )));
+            }
+            if !iso4217::is_valid_custom_ticker(&ticker) {
+                return Err(Error::msg(format!("Invalid ticker: {}", ticker)));
+            }
+        }
+        if name.trim().is_empty() {
+            return Err(Error::msg("Currency name is empty"));
+        }
         self.dao.create(name, ticker).await
     }
 
-    pub async fn add_rate(&self, currency: &Currency, rate: Decimal) -> Result<(), Error> {
-        self.dao.add_rate(currency.id, rate).await?;
+    pub async fn add_rate(
+        &self,
+        currency: &Currency,
+        rate: Rate,
+        source: &RateSource,
+    ) -> Result<(), Error> {
+        self.dao
+            .add_rate(currency.id, rate, &source.to_string())
+            .await?;
         Ok(())
     }
+
+    pub async fn add_rate_on(
+        &self,
+        currency: &Currency,
+        rate: Rate,
+        date: NaiveDateTime,
+        source: &RateSource,
+    ) -> Result<i64, Error> {
+        self.dao
+            .add_rate_on(currency.id, rate, date, &source.to_string())
+            .await
+    }
+
+    pub async fn rate_on(
+        &self,
+        currency: &Currency,
+        date: NaiveDateTime,
+    ) -> Result<Option<CurrencyRate>, Error> {
+        self.dao.find_rate_on(currency.id, date).await
+    }
+
+    /// Rate in effect at `date`.
+    pub async fn rate_at(
+        &self,
+        currency: &Currency,
+        date: NaiveDateTime,
+    ) -> Result<Option<CurrencyRate>, Error> {
+        self.dao.find_rate_at(currency.id, date).await
+    }
+
+    /// Price of one unit of the currency in the base currency at `date`, `None` without a rate.
+    /// The base currency is worth 1 by definition and may have no history.
+    pub async fn rate_value_at(
+        &self,
+        currency: &Currency,
+        date: NaiveDateTime,
+    ) -> Result<Option<Rate>, Error> {
+        if currency.id == self.base_currency().await?.id {
+            return Ok(Some(Rate::int(1)));
+        }
+        let rate = self.dao.find_rate_at(currency.id, date).await?;
+        Ok(rate.map(|r| r.rate))
+    }
+
+    pub async fn get_rate(&self, id: i64) -> Result<Option<CurrencyRate>, Error> {
+        self.dao.get_rate(id).await
+    }
+
+    /// Writes a new version of the rate. Operations keep the version they were booked with.
+    pub async fn correct_rate(
+        &self,
+        rate: &CurrencyRate,
+        value: Rate,
+        source: &RateSource,
+    ) -> Result<CurrencyRate, Error> {
+        let id = self
+            .dao
+            .correct_rate(rate, value, &source.to_string())
+            .await?;
+        self.dao
+            .get_rate(id)
+            .await?
+            .ok_or(Error::msg("Corrected rate not found"))
+    }
+
+    pub async fn rate_history(
+        &self,
+        currency: &Currency,
+        date: NaiveDateTime,
+    ) -> Result<Vec<CurrencyRate>, Error> {
+        self.dao.get_rate_history(currency.id, date).await
+    }
+
+    pub async fn rates(&self, currency: &Currency) -> Result<Vec<CurrencyRate>, Error> {
+        self.dao.get_rates(currency.id).await
+    }
+
+    /// Ids of the currency rates used by operations.
+    pub async fn referenced_rates(&self, currency: &Currency) -> Result<Vec<i64>, Error> {
+        self.dao.referenced_rates(currency.id).await
+    }
+
+    pub async fn drop_unreferenced_rates(&self, rates: &[CurrencyRate]) -> Result<u64, Error> {
+        self.dao.drop_unreferenced_rates(rates).await
+    }
+
+    pub async fn drop_rate(&self, rate: &CurrencyRate) -> Result<(), Error> {
+        if self.dao.count_rate_operations(rate.id).await? > 0 {
+            return Err(Error::msg("Rate is used by operations"));
+        }
+        self.dao.drop_rate(rate.id).await
+    }
 }
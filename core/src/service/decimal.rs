@@ -4,6 +4,23 @@ use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 use std::str::FromStr;
 
 const DECIMALS: u8 = 2;
+const RATE_DECIMALS: u8 = 8;
+
+/// Raw value with `decimals` more decimal places.
+fn scaled(val: i64, decimals: u8) -> i128 {
+    val as i128 * 10i128.pow(decimals as u32)
+}
+
+/// Divides rounding half away from zero.
+fn round_div(num: i128, den: i128) -> i128 {
+    let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+    let half = den / 2;
+    if num >= 0 {
+        (num + half) / den
+    } else {
+        (num - half) / den
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Decimal(i64);
@@ -12,6 +29,16 @@ impl Decimal {
     pub fn int(val: i64) -> Self {
         Self(val * 10i64.pow(DECIMALS as u32))
     }
+
+    /// Builds decimal from exact `num / den` fraction, rounding half away from zero.
+    pub fn from_ratio(num: i128, den: i128) -> Self {
+        Self(round_div(num * 10i128.pow(DECIMALS as u32), den) as i64)
+    }
+
+    /// Rounds the float to the nearest decimal.
+    pub fn from_f64(val: f64) -> Self {
+        Self((val * 10f64.powi(DECIMALS as i32)).round() as i64)
+    }
 }
 
 pub fn dec(val: &str) -> Decimal {
@@ -25,6 +52,10 @@ impl FromStr for Decimal {
         let mut parts = s.split('.');
         let integer = parts.next().unwrap_or("0");
         let fractional = parts.next().unwrap_or("0");
+        if fractional.len() > DECIMALS as usize {
+            let value = format!("{}{}", integer, fractional).parse::<i128>()?;
+            return Ok(Self::from_ratio(value, 10i128.pow(fractional.len() as u32)));
+        }
         let fractional = format!("{:0<2}", fractional);
         let value = format!("{}{}", integer, fractional);
         let value = value.parse::<i64>()?;
@@ -50,6 +81,12 @@ impl From<Decimal> for i64 {
     }
 }
 
+impl From<Decimal> for f64 {
+    fn from(val: Decimal) -> Self {
+        val.0 as f64 / 10i64.pow(DECIMALS as u32) as f64
+    }
+}
+
 impl Add for Decimal {
     type Output = Self;
 
@@ -114,6 +151,99 @@ impl DivAssign for Decimal {
     }
 }
 
+/// Currency rate, kept with more decimals than amounts so that rates of
+/// small currency units survive conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Rate(i64);
+
+impl Rate {
+    pub fn int(val: i64) -> Self {
+        Self(val * 10i64.pow(RATE_DECIMALS as u32))
+    }
+
+    /// Builds rate from exact `num / den` fraction, rounding half away from zero.
+    pub fn from_ratio(num: i128, den: i128) -> Self {
+        Self(round_div(num * 10i128.pow(RATE_DECIMALS as u32), den) as i64)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Rounds the rate to decimal precision.
+    pub fn to_decimal(self) -> Decimal {
+        Decimal(round_div(
+            self.0 as i128,
+            10i128.pow((RATE_DECIMALS - DECIMALS) as u32),
+        ) as i64)
+    }
+}
+
+pub fn rate(val: &str) -> Rate {
+    Rate::from_str(val).unwrap()
+}
+
+impl FromStr for Rate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('.');
+        let integer = parts.next().unwrap_or("0");
+        let fractional = parts.next().unwrap_or("");
+        let value = format!("{}{}", integer, fractional).parse::<i128>()?;
+        Ok(Self::from_ratio(value, 10i128.pow(fractional.len() as u32)))
+    }
+}
+
+impl Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", f64::from(*self))
+    }
+}
+
+impl From<i64> for Rate {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Rate> for i64 {
+    fn from(val: Rate) -> Self {
+        val.0
+    }
+}
+
+impl From<Decimal> for Rate {
+    fn from(val: Decimal) -> Self {
+        Self(val.0 * 10i64.pow((RATE_DECIMALS - DECIMALS) as u32))
+    }
+}
+
+impl From<Rate> for f64 {
+    fn from(val: Rate) -> Self {
+        val.0 as f64 / 10i64.pow(RATE_DECIMALS as u32) as f64
+    }
+}
+
+impl Mul<Rate> for Decimal {
+    type Output = Decimal;
+
+    fn mul(self, rhs: Rate) -> Self::Output {
+        Decimal(round_div(
+            self.0 as i128 * rhs.0 as i128,
+            10i128.pow(RATE_DECIMALS as u32),
+        ) as i64)
+    }
+}
+
+impl Div<Rate> for Decimal {
+    type Output = Decimal;
+
+    fn div(self, rhs: Rate) -> Self::Output {
+        Decimal(round_div(scaled(self.0, RATE_DECIMALS), rhs.0 as i128) as i64)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +271,18 @@ mod tests {
         assert_eq!(Decimal::from_str("101.01").unwrap().0, 10101);
         assert_eq!(Decimal::from_str("1010.1").unwrap().0, 101010);
         assert_eq!(Decimal::from_str("10101.01").unwrap().0, 1010101);
+        assert_eq!(Decimal::from_str("1.0539").unwrap().0, 105);
+        assert_eq!(Decimal::from_str("96.5051").unwrap().0, 9651);
+        assert_eq!(Decimal::from_str("-0.125").unwrap().0, -13);
+    }
+
+    #[test]
+    fn test_balance_f64() {
+        assert_eq!(f64::from(Decimal(10101)), 101.01);
+        assert_eq!(f64::from(Decimal(-13)), -0.13);
+        assert_eq!(Decimal::from_f64(101.01), Decimal(10101));
+        assert_eq!(Decimal::from_f64(0.125), Decimal(13));
+        assert_eq!(Decimal::from_f64(-0.125), Decimal(-13));
     }
 
     #[test]
@@ -234,6 +376,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_ratio() {
+        assert_eq!(Decimal::from_ratio(1, 3), dec("0.33"));
+        assert_eq!(Decimal::from_ratio(2, 3), dec("0.67"));
+        assert_eq!(Decimal::from_ratio(-2, 3), Decimal(-67));
+        assert_eq!(Decimal::from_ratio(2, -3), Decimal(-67));
+        assert_eq!(Decimal::from_ratio(524523, 1000000), dec("0.52"));
+        assert_eq!(Decimal::from_ratio(10, 1), dec("10"));
+    }
+
     #[test]
     fn test_div() {
         assert_eq!(
@@ -245,4 +397,17 @@ mod tests {
             Decimal::from_str("3.33").unwrap()
         );
     }
+
+    #[test]
+    fn test_rate() {
+        assert_eq!(rate("0.0067").0, 670000);
+        assert_eq!(rate("96.5051").0, 9650510000);
+        assert_eq!(rate("1").0, 100000000);
+        assert_eq!(Rate::from_ratio(1, 150), Rate(666667));
+        assert_eq!(Rate::from(dec("1.05")), rate("1.05"));
+        assert_eq!(rate("1.055").to_decimal(), dec("1.06"));
+        assert_eq!(dec("15000") * Rate::from_ratio(1, 150), dec("100"));
+        assert_eq!(dec("1") / rate("0.0125"), dec("80"));
+        assert_eq!(rate("0.00666667").to_string(), "0.00666667");
+    }
 }
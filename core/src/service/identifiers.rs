@@ -0,0 +1,231 @@
+use crate::dao::model::assets::InstrumentClass;
+use color_eyre::eyre::Error;
+use std::str::FromStr;
+
+/// Asset lookup key.
+/// Parsed from `isin:<ISIN>`, `figi:<FIGI>`, `cusip:<CUSIP>`, `<TICKER>@<MIC>` or a plain ticker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetKey {
+    /// Ticker listed on a single exchange.
+    Ticker(String),
+    Listing {
+        ticker: String,
+        exchange: String,
+    },
+    Isin(String),
+    Figi(String),
+    Cusip(String),
+}
+
+impl FromStr for AssetKey {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if let Some((scheme, id)) = value.split_once(':') {
+            let id = id.trim().to_uppercase();
+            return match scheme.trim().to_lowercase().as_str() {
+                "isin" => Ok(AssetKey::Isin(id)),
+                "figi" => Ok(AssetKey::Figi(id)),
+                "cusip" => Ok(AssetKey::Cusip(id)),
+                _ => Err(Error::msg(format!("Unknown identifier scheme: {}", scheme))),
+            };
+        }
+        if let Some((ticker, exchange)) = value.split_once('@') {
+            return Ok(AssetKey::Listing {
+                ticker: ticker.trim().to_string(),
+                exchange: exchange.trim().to_uppercase(),
+            });
+        }
+        if value.is_empty() {
+            return Err(Error::msg("Asset identifier is empty"));
+        }
+        Ok(AssetKey::Ticker(value.to_string()))
+    }
+}
+
+/// Identifiers and classification of an asset.
+#[derive(Debug, Clone)]
+pub struct AssetIdentifiers {
+    /// Market identifier code, empty if unknown.
+    pub exchange: String,
+    pub isin: Option<String>,
+    pub figi: Option<String>,
+    pub cusip: Option<String>,
+    pub instrument_class: InstrumentClass,
+}
+
+impl Default for AssetIdentifiers {
+    fn default() -> Self {
+        Self {
+            exchange: String::new(),
+            isin: None,
+            figi: None,
+            cusip: None,
+            instrument_class: InstrumentClass::Equity,
+        }
+    }
+}
+
+impl AssetIdentifiers {
+    /// Upper cases identifiers and checks their check digits.
+    pub fn normalize(self) -> Result<Self, Error> {
+        let upper = |val: Option<String>| {
+            val.map(|v| v.trim().to_uppercase())
+                .filter(|v| !v.is_empty())
+        };
+        let ids = Self {
+            exchange: self.exchange.trim().to_uppercase(),
+            isin: upper(self.isin),
+            figi: upper(self.figi),
+            cusip: upper(self.cusip),
+            instrument_class: self.instrument_class,
+        };
+        if ids.isin.as_deref().is_some_and(|v| !is_valid_isin(v)) {
+            return Err(Error::msg("Invalid ISIN"));
+        }
+        if ids.figi.as_deref().is_some_and(|v| !is_valid_figi(v)) {
+            return Err(Error::msg("Invalid FIGI"));
+        }
+        if ids.cusip.as_deref().is_some_and(|v| !is_valid_cusip(v)) {
+            return Err(Error::msg("Invalid CUSIP"));
+        }
+        Ok(ids)
+    }
+}
+
+/// Value of an identifier character: digits as is, letters from 10.
+fn char_value(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' | 'A'..='Z' => c.to_digit(36),
+        _ => None,
+    }
+}
+
+/// Two letter country code, nine alphanumerics and a Luhn check digit.
+pub fn is_valid_isin(isin: &str) -> bool {
+    if isin.len() != 12
+        || !isin.is_ascii()
+        || !isin[..2].chars().all(|c| c.is_ascii_uppercase())
+        || !isin[11..].chars().all(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+    let mut digits = Vec::new();
+    for c in isin.chars() {
+        match char_value(c) {
+            Some(v) if v >= 10 => digits.extend([v / 10, v % 10]),
+            Some(v) => digits.push(v),
+            None => return false,
+        }
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            let d = if i % 2 == 1 { d * 2 } else { *d };
+            d / 10 + d % 10
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Check digit shared by CUSIP and FIGI: letters from 10, every second value doubled.
+fn check_digit(payload: &str) -> Option<u32> {
+    let mut sum = 0;
+    for (i, c) in payload.chars().enumerate() {
+        let mut v = match c {
+            '*' => 36,
+            '@' => 37,
+            '#' => 38,
+            c => char_value(c)?,
+        };
+        if i % 2 == 1 {
+            v *= 2;
+        }
+        sum += v / 10 + v % 10;
+    }
+    Some((10 - sum % 10) % 10)
+}
+
+fn has_check_digit(id: &str) -> bool {
+    let (payload, check) = id.split_at(id.len() - 1);
+    check.chars().next().and_then(|c| c.to_digit(10)) == check_digit(payload)
+}
+
+pub fn is_valid_cusip(cusip: &str) -> bool {
+    cusip.len() == 9 && cusip.is_ascii() && has_check_digit(cusip)
+}
+
+/// Twelve characters without vowels, `G` at the third position and a check digit.
+pub fn is_valid_figi(figi: &str) -> bool {
+    figi.len() == 12
+        && figi.is_ascii()
+        && figi.as_bytes()[2] == b'G'
+        && !figi.chars().any(|c| "AEIOU".contains(c))
+        && has_check_digit(figi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(
+            AssetKey::from_str("isin:us0378331005").unwrap(),
+            AssetKey::Isin("US0378331005".to_string())
+        );
+        assert_eq!(
+            AssetKey::from_str("SBER@misx").unwrap(),
+            AssetKey::Listing {
+                ticker: "SBER".to_string(),
+                exchange: "MISX".to_string()
+            }
+        );
+        assert_eq!(
+            AssetKey::from_str("AAPL").unwrap(),
+            AssetKey::Ticker("AAPL".to_string())
+        );
+        assert!(AssetKey::from_str("sedol:2046251").is_err());
+        assert!(AssetKey::from_str(" ").is_err());
+    }
+
+    #[test]
+    fn test_check_digits() {
+        assert!(is_valid_isin("US0378331005"));
+        assert!(is_valid_isin("RU0009029540"));
+        assert!(!is_valid_isin("US0378331006"));
+        assert!(!is_valid_isin("US037833100"));
+        assert!(!is_valid_isin("AÉ037833100"));
+
+        assert!(is_valid_cusip("037833100"));
+        assert!(!is_valid_cusip("037833101"));
+
+        assert!(is_valid_figi("BBG000B9XRY4"));
+        assert!(!is_valid_figi("BBG000B9XRY5"));
+        assert!(!is_valid_figi("BBA000B9XRY4"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let ids = AssetIdentifiers {
+            exchange: " xnas".to_string(),
+            isin: Some("us0378331005".to_string()),
+            figi: Some("".to_string()),
+            ..Default::default()
+        }
+        .normalize()
+        .unwrap();
+        assert_eq!(ids.exchange, "XNAS");
+        assert_eq!(ids.isin.as_deref(), Some("US0378331005"));
+        assert_eq!(ids.figi, None);
+
+        let ids = AssetIdentifiers {
+            cusip: Some("037833101".to_string()),
+            ..Default::default()
+        };
+        assert!(ids.normalize().is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Central Bank of Russia rates.
+//! Rates are quoted as rubles per `Nominal` units of the currency (e.g. per 100 JPY).
+//! Files are served in windows-1251 and must be decoded by the caller.
+
+use crate::service::import::{parse_number, RateImport, RateQuote, Ratio};
+use chrono::{NaiveDate, NaiveDateTime};
+use color_eyre::eyre::Error;
+use roxmltree::Node;
+
+const QUOTE_TICKER: &str = "RUB";
+
+/// Parses daily rates (`XML_daily.asp`).
+pub fn parse_daily(xml: &str) -> Result<RateImport, Error> {
+    let doc = roxmltree::Document::parse(xml)?;
+    let root = doc.root_element();
+    let date = parse_date(root.attribute("Date").ok_or(Error::msg("Date not found"))?)?;
+
+    let mut quotes = Vec::new();
+    for valute in root.children().filter(|n| n.has_tag_name("Valute")) {
+        quotes.push(RateQuote {
+            ticker: child_text(valute, "CharCode")?.to_string(),
+            name: Some(child_text(valute, "Name")?.to_string()),
+            date,
+            price: price(valute)?,
+        });
+    }
+    Ok(RateImport {
+        quote_ticker: QUOTE_TICKER.to_string(),
+        quotes,
+    })
+}
+
+/// Parses dynamic rates of one currency (`XML_dynamic.asp`).
+/// The file has no currency code, so the ticker must be provided.
+pub fn parse_dynamic(xml: &str, ticker: &str) -> Result<RateImport, Error> {
+    let doc = roxmltree::Document::parse(xml)?;
+    let mut quotes = Vec::new();
+    for record in doc
+        .root_element()
+        .children()
+        .filter(|n| n.has_tag_name("Record"))
+    {
+        let date = parse_date(
+            record
+                .attribute("Date")
+                .ok_or(Error::msg("Record without date"))?,
+        )?;
+        quotes.push(RateQuote {
+            ticker: ticker.to_string(),
+            name: None,
+            date,
+            price: price(record)?,
+        });
+    }
+    Ok(RateImport {
+        quote_ticker: QUOTE_TICKER.to_string(),
+        quotes,
+    })
+}
+
+fn price(node: Node) -> Result<Ratio, Error> {
+    let nominal = child_text(node, "Nominal")?
+        .trim()
+        .parse::<i128>()
+        .map_err(|_| Error::msg("Invalid nominal"))?;
+    if nominal <= 0 {
+        return Err(Error::msg("Invalid nominal"));
+    }
+    let (mantissa, scale) = parse_number(child_text(node, "Value")?)?;
+    Ok(Ratio {
+        num: mantissa,
+        den: 10i128.pow(scale) * nominal,
+    })
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, Error> {
+    node.children()
+        .find(|n| n.has_tag_name(name))
+        .and_then(|n| n.text())
+        .ok_or(Error::msg(format!("{} not found", name)))
+}
+
+fn parse_date(value: &str) -> Result<NaiveDateTime, Error> {
+    let date = NaiveDate::parse_from_str(value.trim(), "%d.%m.%Y")?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::rate;
+    use crate::service::test_utils::time;
+
+    const DAILY: &str = r#"<?xml version="1.0" encoding="windows-1251"?>
+<ValCurs Date="28.09.2023" name="Foreign Currency Market">
+    <Valute ID="R01235">
+        <NumCode>840</NumCode>
+        <CharCode>USD</CharCode>
+        <Nominal>1</Nominal>
+        <Name>Доллар США</Name>
+        <Value>96,5044</Value>
+    </Valute>
+    <Valute ID="R01820">
+        <NumCode>392</NumCode>
+        <CharCode>JPY</CharCode>
+        <Nominal>100</Nominal>
+        <Name>Японских иен</Name>
+        <Value>64,6940</Value>
+    </Valute>
+</ValCurs>"#;
+
+    const DYNAMIC: &str = r#"<?xml version="1.0" encoding="windows-1251"?>
+<ValCurs ID="R01235" DateRange1="27.09.2023" DateRange2="28.09.2023" name="Foreign Currency Market Dynamic">
+    <Record Date="27.09.2023" Id="R01235">
+        <Nominal>1</Nominal>
+        <Value>96,2378</Value>
+    </Record>
+    <Record Date="28.09.2023" Id="R01235">
+        <Nominal>1</Nominal>
+        <Value>96,5044</Value>
+    </Record>
+</ValCurs>"#;
+
+    #[test]
+    fn test_parse_daily() {
+        let import = parse_daily(DAILY).unwrap();
+        assert_eq!(import.quote_ticker, "RUB");
+        assert_eq!(import.quotes.len(), 2);
+        assert_eq!(import.quotes[0].ticker, "USD");
+        assert_eq!(import.quotes[0].name, Some("Доллар США".to_string()));
+        assert_eq!(import.quotes[0].price.to_rate(), rate("96.5044"));
+        assert_eq!(import.quotes[1].ticker, "JPY");
+        assert_eq!(import.quotes[1].price.to_rate(), rate("0.64694"));
+    }
+
+    #[test]
+    fn test_parse_dynamic() {
+        let import = parse_dynamic(DYNAMIC, "USD").unwrap();
+        assert_eq!(import.quotes.len(), 2);
+        assert_eq!(import.quotes[0].date, time("2023-09-27 00:00:00"));
+        assert_eq!(import.quotes[0].price.to_rate(), rate("96.2378"));
+    }
+}
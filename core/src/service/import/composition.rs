@@ -0,0 +1,75 @@
+//! Fund composition: share of the fund in each asset type.
+
+use crate::service::decimal::Decimal;
+use color_eyre::eyre::Error;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompositionRow {
+    /// Asset type name.
+    pub tp: String,
+    /// Share of the fund, in percent.
+    pub weight: Decimal,
+}
+
+/// Parses `type,weight` rows. The header is optional, weights may end with `%`.
+/// Rows of the same type are summed up.
+pub fn parse_csv(csv: &str) -> Result<Vec<CompositionRow>, Error> {
+    let mut rows = Vec::<CompositionRow>::new();
+    for (idx, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (tp, weight) = line
+            .rsplit_once(',')
+            .ok_or(Error::msg(format!("Invalid composition row: {}", line)))?;
+        let tp = tp.trim().trim_matches('"').trim();
+        let weight = weight.trim().trim_end_matches('%').trim();
+        let weight = match Decimal::from_str(weight) {
+            Ok(weight) => weight,
+            Err(_) if idx == 0 => continue,
+            Err(_) => return Err(Error::msg(format!("Invalid weight: {}", weight))),
+        };
+        if tp.is_empty() {
+            return Err(Error::msg(format!("Type is missing: {}", line)));
+        }
+        match rows.iter_mut().find(|row| row.tp == tp) {
+            Some(row) => row.weight += weight,
+            None => rows.push(CompositionRow {
+                tp: tp.to_string(),
+                weight,
+            }),
+        }
+    }
+    if rows.is_empty() {
+        return Err(Error::msg("Empty composition"));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+
+    #[test]
+    fn test_parse_csv() {
+        let rows = parse_csv("type,weight\nStocks,55%\n\"Bonds, Gov\",40\nStocks,5.5\n").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                CompositionRow {
+                    tp: "Stocks".to_string(),
+                    weight: dec("60.5"),
+                },
+                CompositionRow {
+                    tp: "Bonds, Gov".to_string(),
+                    weight: dec("40"),
+                },
+            ]
+        );
+        assert!(parse_csv("type,weight\n").is_err());
+        assert!(parse_csv("Stocks,60\nBonds,abc\n").is_err());
+    }
+}
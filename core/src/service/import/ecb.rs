@@ -0,0 +1,143 @@
+//! European Central Bank euro reference rates.
+//! Rates are quoted as units of the currency per one euro.
+
+use crate::service::import::{parse_number, RateImport, RateQuote, Ratio};
+use chrono::{NaiveDate, NaiveDateTime};
+use color_eyre::eyre::Error;
+
+const QUOTE_TICKER: &str = "EUR";
+
+/// Parses `eurofxref-daily.xml`/`eurofxref-hist.xml` files.
+pub fn parse_xml(xml: &str) -> Result<RateImport, Error> {
+    let doc = roxmltree::Document::parse(xml)?;
+    let mut quotes = Vec::new();
+    for day in doc
+        .descendants()
+        .filter(|n| n.has_tag_name("Cube") && n.has_attribute("time"))
+    {
+        let date = parse_date(day.attribute("time").unwrap_or_default())?;
+        for cube in day.children().filter(|n| n.has_tag_name("Cube")) {
+            let ticker = cube
+                .attribute("currency")
+                .ok_or(Error::msg("Cube without currency"))?;
+            let rate = cube
+                .attribute("rate")
+                .ok_or(Error::msg("Cube without rate"))?;
+            quotes.push(quote(ticker, date, rate)?);
+        }
+    }
+    Ok(RateImport {
+        quote_ticker: QUOTE_TICKER.to_string(),
+        quotes,
+    })
+}
+
+/// Parses `eurofxref.csv`/`eurofxref-hist.csv` files. Missing values (`N/A`) are skipped.
+pub fn parse_csv(csv: &str) -> Result<RateImport, Error> {
+    let mut lines = csv.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().ok_or(Error::msg("Empty csv"))?;
+    let tickers = header
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .collect::<Vec<_>>();
+    if tickers.first().is_none_or(|t| t != "Date") {
+        return Err(Error::msg("Date column not found"));
+    }
+
+    let mut quotes = Vec::new();
+    for line in lines {
+        let mut columns = line.split(',');
+        let date = parse_date(columns.next().unwrap_or_default())?;
+        for (ticker, value) in tickers.iter().skip(1).zip(columns) {
+            let value = value.trim();
+            if ticker.is_empty() || value.is_empty() || value == "N/A" {
+                continue;
+            }
+            quotes.push(quote(ticker, date, value)?);
+        }
+    }
+    Ok(RateImport {
+        quote_ticker: QUOTE_TICKER.to_string(),
+        quotes,
+    })
+}
+
+fn quote(ticker: &str, date: NaiveDateTime, rate: &str) -> Result<RateQuote, Error> {
+    let (mantissa, scale) = parse_number(rate)?;
+    Ok(RateQuote {
+        ticker: ticker.to_string(),
+        name: None,
+        date,
+        price: Ratio {
+            num: 10i128.pow(scale),
+            den: mantissa,
+        },
+    })
+}
+
+fn parse_date(value: &str) -> Result<NaiveDateTime, Error> {
+    let date = NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::test_utils::time;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+    <gesmes:subject>Reference rates</gesmes:subject>
+    <gesmes:Sender>
+        <gesmes:name>European Central Bank</gesmes:name>
+    </gesmes:Sender>
+    <Cube>
+        <Cube time="2023-09-28">
+            <Cube currency="USD" rate="1.0539"/>
+            <Cube currency="JPY" rate="157.5"/>
+        </Cube>
+        <Cube time="2023-09-27">
+            <Cube currency="USD" rate="1.0496"/>
+        </Cube>
+    </Cube>
+</gesmes:Envelope>"#;
+
+    const CSV: &str =
+        "Date,USD,JPY,CYP,\n2023-09-28,1.0539,157.5,N/A,\n2023-09-27,1.0496,157.1,N/A,\n";
+
+    #[test]
+    fn test_parse_xml() {
+        let import = parse_xml(XML).unwrap();
+        assert_eq!(import.quote_ticker, "EUR");
+        assert_eq!(import.quotes.len(), 3);
+        assert_eq!(
+            import.quotes[0],
+            RateQuote {
+                ticker: "USD".to_string(),
+                name: None,
+                date: time("2023-09-28 00:00:00"),
+                price: Ratio {
+                    num: 10000,
+                    den: 10539
+                },
+            }
+        );
+        assert_eq!(import.quotes[2].date, time("2023-09-27 00:00:00"));
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let import = parse_csv(CSV).unwrap();
+        assert_eq!(import.quotes.len(), 4);
+        assert_eq!(
+            import.quotes[1],
+            RateQuote {
+                ticker: "JPY".to_string(),
+                name: None,
+                date: time("2023-09-28 00:00:00"),
+                price: Ratio { num: 10, den: 1575 },
+            }
+        );
+        assert!(parse_csv("USD,JPY\n1,2").is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use crate::service::decimal::Rate;
+use chrono::NaiveDateTime;
+use color_eyre::eyre::Error;
+use std::collections::BTreeMap;
+
+pub mod cbr;
+pub mod composition;
+pub mod ecb;
+
+/// Exact price of one unit of quoted currency: `num / den` units of the quote currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    pub num: i128,
+    pub den: i128,
+}
+
+impl Ratio {
+    pub fn to_rate(self) -> Rate {
+        Rate::from_ratio(self.num, self.den)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateQuote {
+    pub ticker: String,
+    pub name: Option<String>,
+    pub date: NaiveDateTime,
+    pub price: Ratio,
+}
+
+/// Rates parsed from a central bank file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateImport {
+    /// Currency all prices are expressed in (EUR for ECB, RUB for CBR).
+    pub quote_ticker: String,
+    pub quotes: Vec<RateQuote>,
+}
+
+/// Rate of a currency in the base currency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseRate {
+    pub ticker: String,
+    pub name: Option<String>,
+    pub date: NaiveDateTime,
+    pub rate: Rate,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    /// New rates written.
+    pub inserted: usize,
+    /// Existing rates corrected with a different value.
+    pub updated: usize,
+    /// Rates already stored with the same value.
+    pub unchanged: usize,
+    /// Currencies created during import.
+    pub created: Vec<String>,
+    /// Tickers skipped because the currency does not exist.
+    pub unknown: Vec<String>,
+    /// Dates skipped because the file has no rate for the base currency.
+    pub skipped_dates: usize,
+}
+
+impl RateImport {
+    /// Converts quotes into rates of the base currency.
+    /// Duplicated quotes for the same ticker and date are collapsed, the last one wins.
+    /// Returns the rates and the number of dates which have no base currency quote.
+    pub fn rebase(&self, base_ticker: &str) -> (Vec<BaseRate>, usize) {
+        let mut by_date = BTreeMap::<NaiveDateTime, BTreeMap<String, &RateQuote>>::new();
+        for quote in &self.quotes {
+            by_date
+                .entry(quote.date)
+                .or_default()
+                .insert(quote.ticker.to_uppercase(), quote);
+        }
+
+        let base_is_quote = self.quote_ticker.eq_ignore_ascii_case(base_ticker);
+        let mut rates = Vec::new();
+        let mut skipped = 0;
+        for (date, quotes) in by_date {
+            let base_price = if base_is_quote {
+                Ratio { num: 1, den: 1 }
+            } else if let Some(base) = quotes.get(&base_ticker.to_uppercase()) {
+                base.price
+            } else {
+                skipped += 1;
+                continue;
+            };
+
+            if !base_is_quote {
+                rates.push(BaseRate {
+                    ticker: self.quote_ticker.clone(),
+                    name: None,
+                    date,
+                    rate: Ratio {
+                        num: base_price.den,
+                        den: base_price.num,
+                    }
+                    .to_rate(),
+                });
+            }
+
+            for (ticker, quote) in quotes {
+                if ticker.eq_ignore_ascii_case(base_ticker) {
+                    continue;
+                }
+                rates.push(BaseRate {
+                    ticker,
+                    name: quote.name.clone(),
+                    date,
+                    rate: Ratio {
+                        num: quote.price.num * base_price.den,
+                        den: quote.price.den * base_price.num,
+                    }
+                    .to_rate(),
+                });
+            }
+        }
+        (rates, skipped)
+    }
+}
+
+/// Parses decimal number with `.` or `,` separator into mantissa and scale.
+fn parse_number(value: &str) -> Result<(i128, u32), Error> {
+    let value = value.trim().replace(',', ".");
+    let (integer, fractional) = value.split_once('.').unwrap_or((&value, ""));
+    let mantissa = format!("{}{}", integer, fractional)
+        .parse::<i128>()
+        .map_err(|_| Error::msg(format!("Invalid number: {}", value)))?;
+    if mantissa <= 0 {
+        return Err(Error::msg(format!("Invalid rate: {}", value)));
+    }
+    Ok((mantissa, fractional.len() as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::rate;
+    use crate::service::test_utils::time;
+
+    fn quote(ticker: &str, date: &str, num: i128, den: i128) -> RateQuote {
+        RateQuote {
+            ticker: ticker.to_string(),
+            name: None,
+            date: time(date),
+            price: Ratio { num, den },
+        }
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("1.0539").unwrap(), (10539, 4));
+        assert_eq!(parse_number("61,6159").unwrap(), (616159, 4));
+        assert_eq!(parse_number("157").unwrap(), (157, 0));
+        assert!(parse_number("N/A").is_err());
+        assert!(parse_number("0").is_err());
+    }
+
+    #[test]
+    fn test_rebase_same_quote() {
+        let import = RateImport {
+            quote_ticker: "RUB".to_string(),
+            quotes: vec![
+                quote("USD", "2023-09-28 00:00:00", 965, 10),
+                quote("USD", "2023-09-28 00:00:00", 966, 10),
+            ],
+        };
+        let (rates, skipped) = import.rebase("rub");
+        assert_eq!(skipped, 0);
+        assert_eq!(
+            rates,
+            vec![BaseRate {
+                ticker: "USD".to_string(),
+                name: None,
+                date: time("2023-09-28 00:00:00"),
+                rate: rate("96.6"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rebase_cross() {
+        // 1 EUR = 1.05 USD, 1 EUR = 0.86 GBP
+        let import = RateImport {
+            quote_ticker: "EUR".to_string(),
+            quotes: vec![
+                quote("USD", "2023-09-28 00:00:00", 100, 105),
+                quote("GBP", "2023-09-28 00:00:00", 100, 86),
+                quote("GBP", "2023-09-29 00:00:00", 100, 86),
+            ],
+        };
+        let (rates, skipped) = import.rebase("usd");
+        assert_eq!(skipped, 1);
+        assert_eq!(
+            rates,
+            vec![
+                BaseRate {
+                    ticker: "EUR".to_string(),
+                    name: None,
+                    date: time("2023-09-28 00:00:00"),
+                    rate: rate("1.05"),
+                },
+                BaseRate {
+                    ticker: "GBP".to_string(),
+                    name: None,
+                    date: time("2023-09-28 00:00:00"),
+                    rate: rate("1.22093023"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rebase_sub_cent() {
+        // 1 USD = 96.5044 RUB, 100 JPY = 64.694 RUB
+        let import = RateImport {
+            quote_ticker: "RUB".to_string(),
+            quotes: vec![
+                quote("USD", "2023-09-28 00:00:00", 965044, 10000),
+                quote("JPY", "2023-09-28 00:00:00", 646940, 1000000),
+            ],
+        };
+        let (rates, skipped) = import.rebase("usd");
+        assert_eq!(skipped, 0);
+        assert_eq!(
+            rates
+                .iter()
+                .map(|r| (r.ticker.as_str(), r.rate))
+                .collect::<Vec<_>>(),
+            vec![("RUB", rate("0.01036222")), ("JPY", rate("0.00670374"))]
+        );
+    }
+}
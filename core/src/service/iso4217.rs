@@ -0,0 +1,239 @@
+//! ISO 4217 currency catalog.
+
+/// ISO 4217 currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsoCurrency {
+    /// Alphabetic code.
+    pub code: &'static str,
+    /// Numeric code.
+    pub numeric: u16,
+    pub name: &'static str,
+    pub symbol: &'static str,
+    /// Digits after the decimal separator, `None` for precious metals and SDR.
+    pub minor_units: Option<u8>,
+}
+
+const fn iso(
+    code: &'static str,
+    numeric: u16,
+    name: &'static str,
+    symbol: &'static str,
+    minor_units: Option<u8>,
+) -> IsoCurrency {
+    IsoCurrency {
+        code,
+        numeric,
+        name,
+        symbol,
+        minor_units,
+    }
+}
+
+/// Active currencies sorted by code.
+pub const CURRENCIES: &[IsoCurrency] = &[
+    iso("AED", 784, "UAE Dirham", "د.إ", Some(2)),
+    iso("AFN", 971, "Afghani", "؋", Some(2)),
+    iso("ALL", 8, "Lek", "L", Some(2)),
+    iso("AMD", 51, "Armenian Dram", "֏", Some(2)),
+    iso("ANG", 532, "Netherlands Antillean Guilder", "ƒ", Some(2)),
+    iso("AOA", 973, "Kwanza", "Kz", Some(2)),
+    iso("ARS", 32, "Argentine Peso", "$", Some(2)),
+    iso("AUD", 36, "Australian Dollar", "$", Some(2)),
+    iso("AWG", 533, "Aruban Florin", "ƒ", Some(2)),
+    iso("AZN", 944, "Azerbaijan Manat", "₼", Some(2)),
+    iso("BAM", 977, "Convertible Mark", "KM", Some(2)),
+    iso("BBD", 52, "Barbados Dollar", "$", Some(2)),
+    iso("BDT", 50, "Taka", "৳", Some(2)),
+    iso("BGN", 975, "Bulgarian Lev", "лв", Some(2)),
+    iso("BHD", 48, "Bahraini Dinar", ".د.ب", Some(3)),
+    iso("BIF", 108, "Burundi Franc", "FBu", Some(0)),
+    iso("BMD", 60, "Bermudian Dollar", "$", Some(2)),
+    iso("BND", 96, "Brunei Dollar", "$", Some(2)),
+    iso("BOB", 68, "Boliviano", "Bs.", Some(2)),
+    iso("BRL", 986, "Brazilian Real", "R$", Some(2)),
+    iso("BSD", 44, "Bahamian Dollar", "$", Some(2)),
+    iso("BTN", 64, "Ngultrum", "Nu.", Some(2)),
+    iso("BWP", 72, "Pula", "P", Some(2)),
+    iso("BYN", 933, "Belarusian Ruble", "Br", Some(2)),
+    iso("BZD", 84, "Belize Dollar", "$", Some(2)),
+    iso("CAD", 124, "Canadian Dollar", "$", Some(2)),
+    iso("CDF", 976, "Congolese Franc", "FC", Some(2)),
+    iso("CHF", 756, "Swiss Franc", "CHF", Some(2)),
+    iso("CLP", 152, "Chilean Peso", "$", Some(0)),
+    iso("CNY", 156, "Yuan Renminbi", "¥", Some(2)),
+    iso("COP", 170, "Colombian Peso", "$", Some(2)),
+    iso("CRC", 188, "Costa Rican Colon", "₡", Some(2)),
+    iso("CUP", 192, "Cuban Peso", "$", Some(2)),
+    iso("CVE", 132, "Cabo Verde Escudo", "$", Some(2)),
+    iso("CZK", 203, "Czech Koruna", "Kč", Some(2)),
+    iso("DJF", 262, "Djibouti Franc", "Fdj", Some(0)),
+    iso("DKK", 208, "Danish Krone", "kr", Some(2)),
+    iso("DOP", 214, "Dominican Peso", "$", Some(2)),
+    iso("DZD", 12, "Algerian Dinar", "د.ج", Some(2)),
+    iso("EGP", 818, "Egyptian Pound", "£", Some(2)),
+    iso("ERN", 232, "Nakfa", "Nfk", Some(2)),
+    iso("ETB", 230, "Ethiopian Birr", "Br", Some(2)),
+    iso("EUR", 978, "Euro", "€", Some(2)),
+    iso("FJD", 242, "Fiji Dollar", "$", Some(2)),
+    iso("FKP", 238, "Falkland Islands Pound", "£", Some(2)),
+    iso("GBP", 826, "Pound Sterling", "£", Some(2)),
+    iso("GEL", 981, "Lari", "₾", Some(2)),
+    iso("GHS", 936, "Ghana Cedi", "₵", Some(2)),
+    iso("GIP", 292, "Gibraltar Pound", "£", Some(2)),
+    iso("GMD", 270, "Dalasi", "D", Some(2)),
+    iso("GNF", 324, "Guinean Franc", "FG", Some(0)),
+    iso("GTQ", 320, "Quetzal", "Q", Some(2)),
+    iso("GYD", 328, "Guyana Dollar", "$", Some(2)),
+    iso("HKD", 344, "Hong Kong Dollar", "$", Some(2)),
+    iso("HNL", 340, "Lempira", "L", Some(2)),
+    iso("HTG", 332, "Gourde", "G", Some(2)),
+    iso("HUF", 348, "Forint", "Ft", Some(2)),
+    iso("IDR", 360, "Rupiah", "Rp", Some(2)),
+    iso("ILS", 376, "New Israeli Sheqel", "₪", Some(2)),
+    iso("INR", 356, "Indian Rupee", "₹", Some(2)),
+    iso("IQD", 368, "Iraqi Dinar", "ع.د", Some(3)),
+    iso("IRR", 364, "Iranian Rial", "﷼", Some(2)),
+    iso("ISK", 352, "Iceland Krona", "kr", Some(0)),
+    iso("JMD", 388, "Jamaican Dollar", "$", Some(2)),
+    iso("JOD", 400, "Jordanian Dinar", "د.ا", Some(3)),
+    iso("JPY", 392, "Yen", "¥", Some(0)),
+    iso("KES", 404, "Kenyan Shilling", "KSh", Some(2)),
+    iso("KGS", 417, "Som", "с", Some(2)),
+    iso("KHR", 116, "Riel", "៛", Some(2)),
+    iso("KMF", 174, "Comorian Franc", "CF", Some(0)),
+    iso("KPW", 408, "North Korean Won", "₩", Some(2)),
+    iso("KRW", 410, "Won", "₩", Some(0)),
+    iso("KWD", 414, "Kuwaiti Dinar", "د.ك", Some(3)),
+    iso("KYD", 136, "Cayman Islands Dollar", "$", Some(2)),
+    iso("KZT", 398, "Tenge", "₸", Some(2)),
+    iso("LAK", 418, "Lao Kip", "₭", Some(2)),
+    iso("LBP", 422, "Lebanese Pound", "ل.ل", Some(2)),
+    iso("LKR", 144, "Sri Lanka Rupee", "Rs", Some(2)),
+    iso("LRD", 430, "Liberian Dollar", "$", Some(2)),
+    iso("LSL", 426, "Loti", "L", Some(2)),
+    iso("LYD", 434, "Libyan Dinar", "ل.د", Some(3)),
+    iso("MAD", 504, "Moroccan Dirham", "د.م.", Some(2)),
+    iso("MDL", 498, "Moldovan Leu", "L", Some(2)),
+    iso("MGA", 969, "Malagasy Ariary", "Ar", Some(2)),
+    iso("MKD", 807, "Denar", "ден", Some(2)),
+    iso("MMK", 104, "Kyat", "K", Some(2)),
+    iso("MNT", 496, "Tugrik", "₮", Some(2)),
+    iso("MOP", 446, "Pataca", "MOP$", Some(2)),
+    iso("MRU", 929, "Ouguiya", "UM", Some(2)),
+    iso("MUR", 480, "Mauritius Rupee", "₨", Some(2)),
+    iso("MVR", 462, "Rufiyaa", "Rf", Some(2)),
+    iso("MWK", 454, "Malawi Kwacha", "MK", Some(2)),
+    iso("MXN", 484, "Mexican Peso", "$", Some(2)),
+    iso("MYR", 458, "Malaysian Ringgit", "RM", Some(2)),
+    iso("MZN", 943, "Mozambique Metical", "MT", Some(2)),
+    iso("NAD", 516, "Namibia Dollar", "$", Some(2)),
+    iso("NGN", 566, "Naira", "₦", Some(2)),
+    iso("NIO", 558, "Cordoba Oro", "C$", Some(2)),
+    iso("NOK", 578, "Norwegian Krone", "kr", Some(2)),
+    iso("NPR", 524, "Nepalese Rupee", "₨", Some(2)),
+    iso("NZD", 554, "New Zealand Dollar", "$", Some(2)),
+    iso("OMR", 512, "Rial Omani", "ر.ع.", Some(3)),
+    iso("PAB", 590, "Balboa", "B/.", Some(2)),
+    iso("PEN", 604, "Sol", "S/", Some(2)),
+    iso("PGK", 598, "Kina", "K", Some(2)),
+    iso("PHP", 608, "Philippine Peso", "₱", Some(2)),
+    iso("PKR", 586, "Pakistan Rupee", "₨", Some(2)),
+    iso("PLN", 985, "Zloty", "zł", Some(2)),
+    iso("PYG", 600, "Guarani", "₲", Some(0)),
+    iso("QAR", 634, "Qatari Rial", "ر.ق", Some(2)),
+    iso("RON", 946, "Romanian Leu", "lei", Some(2)),
+    iso("RSD", 941, "Serbian Dinar", "дин.", Some(2)),
+    iso("RUB", 643, "Russian Ruble", "₽", Some(2)),
+    iso("RWF", 646, "Rwanda Franc", "FRw", Some(0)),
+    iso("SAR", 682, "Saudi Riyal", "ر.س", Some(2)),
+    iso("SBD", 90, "Solomon Islands Dollar", "$", Some(2)),
+    iso("SCR", 690, "Seychelles Rupee", "₨", Some(2)),
+    iso("SDG", 938, "Sudanese Pound", "£", Some(2)),
+    iso("SEK", 752, "Swedish Krona", "kr", Some(2)),
+    iso("SGD", 702, "Singapore Dollar", "$", Some(2)),
+    iso("SHP", 654, "Saint Helena Pound", "£", Some(2)),
+    iso("SLE", 925, "Leone", "Le", Some(2)),
+    iso("SOS", 706, "Somali Shilling", "Sh", Some(2)),
+    iso("SRD", 968, "Surinam Dollar", "$", Some(2)),
+    iso("SSP", 728, "South Sudanese Pound", "£", Some(2)),
+    iso("STN", 930, "Dobra", "Db", Some(2)),
+    iso("SVC", 222, "El Salvador Colon", "₡", Some(2)),
+    iso("SYP", 760, "Syrian Pound", "£", Some(2)),
+    iso("SZL", 748, "Lilangeni", "E", Some(2)),
+    iso("THB", 764, "Baht", "฿", Some(2)),
+    iso("TJS", 972, "Somoni", "SM", Some(2)),
+    iso("TMT", 934, "Turkmenistan New Manat", "m", Some(2)),
+    iso("TND", 788, "Tunisian Dinar", "د.ت", Some(3)),
+    iso("TOP", 776, "Pa'anga", "T$", Some(2)),
+    iso("TRY", 949, "Turkish Lira", "₺", Some(2)),
+    iso("TTD", 780, "Trinidad and Tobago Dollar", "$", Some(2)),
+    iso("TWD", 901, "New Taiwan Dollar", "$", Some(2)),
+    iso("TZS", 834, "Tanzanian Shilling", "Sh", Some(2)),
+    iso("UAH", 980, "Hryvnia", "₴", Some(2)),
+    iso("UGX", 800, "Uganda Shilling", "USh", Some(0)),
+    iso("USD", 840, "US Dollar", "$", Some(2)),
+    iso("UYU", 858, "Peso Uruguayo", "$", Some(2)),
+    iso("UZS", 860, "Uzbekistan Sum", "so'm", Some(2)),
+    iso("VES", 928, "Bolivar Soberano", "Bs.S", Some(2)),
+    iso("VND", 704, "Dong", "₫", Some(0)),
+    iso("VUV", 548, "Vatu", "VT", Some(0)),
+    iso("WST", 882, "Tala", "T", Some(2)),
+    iso("XAF", 950, "CFA Franc BEAC", "FCFA", Some(0)),
+    iso("XAG", 961, "Silver", "XAG", None),
+    iso("XAU", 959, "Gold", "XAU", None),
+    iso("XCD", 951, "East Caribbean Dollar", "$", Some(2)),
+    iso("XDR", 960, "SDR (Special Drawing Right)", "SDR", None),
+    iso("XOF", 952, "CFA Franc BCEAO", "CFA", Some(0)),
+    iso("XPD", 964, "Palladium", "XPD", None),
+    iso("XPF", 953, "CFP Franc", "₣", Some(0)),
+    iso("XPT", 962, "Platinum", "XPT", None),
+    iso("YER", 886, "Yemeni Rial", "﷼", Some(2)),
+    iso("ZAR", 710, "Rand", "R", Some(2)),
+    iso("ZMW", 967, "Zambian Kwacha", "ZK", Some(2)),
+    iso("ZWL", 932, "Zimbabwe Dollar", "$", Some(2)),
+];
+
+/// Normalizes currency ticker: trims and converts it to upper case.
+pub fn normalize_ticker(ticker: &str) -> String {
+    ticker.trim().to_uppercase()
+}
+
+/// Finds currency by alphabetic code, case insensitive.
+pub fn find(code: &str) -> Option<&'static IsoCurrency> {
+    let code = normalize_ticker(code);
+    CURRENCIES
+        .binary_search_by(|c| c.code.cmp(code.as_str()))
+        .ok()
+        .map(|idx| &CURRENCIES[idx])
+}
+
+/// Checks custom (non ISO) ticker: 1-10 ASCII letters or digits.
+pub fn is_valid_custom_ticker(ticker: &str) -> bool {
+    (1..=10).contains(&ticker.len()) && ticker.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_sorted() {
+        assert!(CURRENCIES.windows(2).all(|w| w[0].code < w[1].code));
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find("usd").map(|c| c.numeric), Some(840));
+        assert_eq!(find(" JPY ").and_then(|c| c.minor_units), Some(0));
+        assert_eq!(find("XAU").and_then(|c| c.minor_units), None);
+        assert!(find("BTC").is_none());
+    }
+
+    #[test]
+    fn test_custom_ticker() {
+        assert!(is_valid_custom_ticker("BTC"));
+        assert!(is_valid_custom_ticker("1INCH"));
+        assert!(!is_valid_custom_ticker(""));
+        assert!(!is_valid_custom_ticker("BTC-USD"));
+    }
+}
@@ -1,28 +1,90 @@
+use crate::dao::account::AccountDao;
 use crate::dao::assets::AssetsDao;
+use crate::dao::cash::CashDao;
 use crate::dao::currency::CurrencyDao;
-use crate::dao::model::assets::Asset;
-use crate::dao::model::currency::Currency;
-use crate::dao::model::operations::OperationType;
-use crate::service::decimal::Decimal;
-use crate::service::statistics::assets::{AssetSnapshot, AssetStatistic};
+use crate::dao::fetch_status::FetchStatusDao;
+use crate::dao::model::account::{Account, AccountType, DEFAULT_ACCOUNT};
+use crate::dao::model::assets::{Asset, AssetType, Classification};
+use crate::dao::model::cash::CashExchange;
+use crate::dao::model::corporate_action::CorporateActionType;
+use crate::dao::model::currency::{Currency, CurrencyRate, RateSource};
+use crate::dao::model::operations::{AssetOperation, OperationType};
+use crate::dao::model::target::DEFAULT_DRIFT_BAND;
+use crate::dao::target::TargetDao;
+use crate::service::category::{subtree, ChildTypes, ClassificationTree};
+use crate::service::decimal::{Decimal, Rate};
+use crate::service::identifiers::AssetIdentifiers;
+use crate::service::import::{composition, ImportReport, RateImport};
+use crate::service::retention::{
+    plan_compaction, CompactionReport, CurrencyCompaction, RetentionPolicy,
+};
+use crate::service::statistics::account::{AccountStatistic, AccountsStatistic, Totals};
+use crate::service::statistics::assets::{
+    ActionEffect, AssetSnapshot, AssetStatistic, BaseSnapshot, PositionChange,
+};
+use crate::service::statistics::attribution::{
+    attribution, by_type, Attribution, AttributionReport,
+};
+use crate::service::statistics::benchmark::{compare, Comparison};
+use crate::service::statistics::cash::{cash_flow, fx_cost, FxCost};
+use crate::service::statistics::correlation::{correlation, AlignedSeries, CorrelationMatrix};
+use crate::service::statistics::currency::CurrencyStatistic;
+use crate::service::statistics::exposure::{exposure, ExposureReport};
+use crate::service::statistics::performance::{
+    performance, sample_dates, value_series, AssetHistory, PerformanceTable, Period, Valuation,
+};
+use crate::service::statistics::rebalance::{rebalance, Holding, RebalanceOptions, RebalancePlan};
+use crate::service::statistics::risk::{
+    periodic_rate, periods_per_year, returns, risk, RiskFree, RiskMetrics, RiskOptions,
+};
+use crate::service::statistics::rolling::{with_overlays, Overlay, OverlayGraph};
+use crate::service::statistics::target::{drift, TargetDrift};
+use crate::service::statistics::types::{breakdown, type_weights, Breakdown};
+use crate::service::validation::{ValidationRules, Violation};
+use chrono::{Duration, NaiveDateTime, Utc};
 use color_eyre::eyre::Error;
 use sqlx::{Pool, Sqlite};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
 
+pub mod account;
 pub mod assets;
+pub mod cash;
+pub mod category;
 pub mod currency;
 pub mod decimal;
+pub mod identifiers;
+pub mod import;
+pub mod iso4217;
+pub mod providers;
+pub mod retention;
 pub mod statistics;
+pub mod target;
+#[cfg(test)]
+pub mod test_utils;
+pub mod validation;
 
 pub struct FinanceService {
+    pub account: account::AccountService,
     pub currency: currency::CurrencyService,
     pub assets: assets::AssetsService,
+    pub cash: cash::CashService,
+    pub fetch_status: providers::FetchStatusService,
+    pub target: target::TargetService,
+    pub validation: ValidationRules,
 }
 
 impl FinanceService {
     pub fn new(pool: Pool<Sqlite>) -> Self {
         Self {
+            account: account::AccountService::new(AccountDao::new(pool.clone())),
             currency: currency::CurrencyService::new(CurrencyDao::new(pool.clone())),
-            assets: assets::AssetsService::new(AssetsDao::new(pool)),
+            assets: assets::AssetsService::new(AssetsDao::new(pool.clone())),
+            cash: cash::CashService::new(CashDao::new(pool.clone())),
+            fetch_status: providers::FetchStatusService::new(FetchStatusDao::new(pool.clone())),
+            target: target::TargetService::new(TargetDao::new(pool)),
+            validation: ValidationRules::default(),
         }
     }
 
@@ -38,14 +100,27 @@ impl FinanceService {
         &self,
         name: String,
         ticker: String,
-        rate: Decimal,
+        rate: Rate,
+        custom: bool,
     ) -> Result<Currency, Error> {
-        let currency = self.currency.create(name.clone(), ticker.clone()).await?;
-        self.currency.add_rate(&currency, rate).await?;
+        let currency = self.currency.create(name, ticker, custom).await?;
+        self.currency
+            .add_rate(&currency, rate, &RateSource::Manual)
+            .await?;
         Ok(currency)
     }
 
-    pub async fn remove_currency(&self, ticker: &String) -> Result<(), Error> {
+    /// Creates a currency from the ISO 4217 catalog.
+    pub async fn seed_currency(&self, code: &str, rate: Rate) -> Result<Currency, Error> {
+        let iso = iso4217::find(code).ok_or(Error::msg("Currency not found in ISO 4217"))?;
+        if self.currency.currency(iso.code).await?.is_some() {
+            return Err(Error::msg("Currency already exists"));
+        }
+        self.create_currency(iso.name.to_string(), iso.code.to_string(), rate, false)
+            .await
+    }
+
+    pub async fn remove_currency(&self, ticker: &str) -> Result<(), Error> {
         let currency = self
             .currency
             .currency(ticker)
@@ -55,20 +130,145 @@ impl FinanceService {
         if !assets.is_empty() {
             return Err(Error::msg("Currency has assets"));
         }
+        let accounts = self.account.list().await?;
+        if accounts.iter().any(|a| a.currency == currency.id) {
+            return Err(Error::msg("Currency has accounts"));
+        }
         self.currency.drop(&currency).await?;
         Ok(())
     }
 
-    pub async fn add_currency_rate(&self, ticker: String, rate: Decimal) -> Result<(), Error> {
+    pub async fn add_currency_rate(
+        &self,
+        ticker: String,
+        rate: Rate,
+        source: RateSource,
+    ) -> Result<(), Error> {
         let currency = self
             .currency
             .currency(&ticker)
             .await?
             .ok_or(Error::msg("Currency not found"))?;
-        self.currency.add_rate(&currency, rate).await?;
+        self.currency.add_rate(&currency, rate, &source).await?;
         Ok(())
     }
 
+    /// Corrects a rate by id. The corrected version stays linked to its operations.
+    pub async fn correct_currency_rate(
+        &self,
+        id: i64,
+        rate: Rate,
+        source: RateSource,
+    ) -> Result<CurrencyRate, Error> {
+        let current = self
+            .currency
+            .get_rate(id)
+            .await?
+            .ok_or(Error::msg("Rate not found"))?;
+        self.currency.correct_rate(&current, rate, &source).await
+    }
+
+    /// Imports historical rates parsed from a central bank file.
+    /// Rates are converted to the base currency and written with their own dates.
+    /// Existing rates for the same date are corrected only when the value differs.
+    pub async fn import_rates(
+        &self,
+        import: RateImport,
+        source: RateSource,
+        create_missing: bool,
+    ) -> Result<ImportReport, Error> {
+        let base = self.base_currency().await?;
+        let (rates, skipped_dates) = import.rebase(&base.ticker);
+        let mut report = ImportReport {
+            skipped_dates,
+            ..Default::default()
+        };
+
+        let mut currencies = self
+            .currency
+            .list()
+            .await?
+            .into_iter()
+            .map(|c| (c.ticker.to_uppercase(), c))
+            .collect::<HashMap<_, _>>();
+        for rate in rates {
+            let currency = match currencies.get(&rate.ticker) {
+                Some(currency) => currency,
+                None if create_missing => {
+                    let iso = iso4217::find(&rate.ticker);
+                    let name = iso
+                        .map(|c| c.name.to_string())
+                        .or(rate.name.clone())
+                        .unwrap_or(rate.ticker.clone());
+                    let currency = self
+                        .currency
+                        .create(name, rate.ticker.clone(), iso.is_none())
+                        .await?;
+                    report.created.push(rate.ticker.clone());
+                    currencies.entry(rate.ticker.clone()).or_insert(currency)
+                }
+                None => {
+                    if !report.unknown.contains(&rate.ticker) {
+                        report.unknown.push(rate.ticker.clone());
+                    }
+                    continue;
+                }
+            };
+
+            match self.currency.rate_on(currency, rate.date).await? {
+                Some(existing) if existing.rate == rate.rate => report.unchanged += 1,
+                Some(existing) => {
+                    self.currency
+                        .correct_rate(&existing, rate.rate, &source)
+                        .await?;
+                    report.updated += 1;
+                }
+                None => {
+                    self.currency
+                        .add_rate_on(currency, rate.rate, rate.date, &source)
+                        .await?;
+                    report.inserted += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Reduces old rate history according to the policy.
+    /// Rates used by operations are always kept.
+    pub async fn compact_rates(&self, policy: RetentionPolicy) -> Result<CompactionReport, Error> {
+        let now = Utc::now().naive_utc();
+        let mut report = CompactionReport::default();
+        for currency in self.currency.list().await? {
+            let rates = self.currency.rates(&currency).await?;
+            let referenced = self
+                .currency
+                .referenced_rates(&currency)
+                .await?
+                .into_iter()
+                .collect::<HashSet<_>>();
+            let (remove, kept) = plan_compaction(&rates, &referenced, now, policy);
+            if remove.is_empty() && kept == 0 {
+                continue;
+            }
+
+            let removed = rates
+                .into_iter()
+                .filter(|r| remove.binary_search(&r.id).is_ok())
+                .collect::<Vec<_>>();
+            let first = removed.iter().map(|r| r.date).min();
+            let last = removed.iter().map(|r| r.date).max();
+            let count = self.currency.drop_unreferenced_rates(&removed).await?;
+            report.currencies.push(CurrencyCompaction {
+                ticker: currency.ticker,
+                removed: count as usize,
+                range: first.zip(last),
+                referenced: kept,
+            });
+        }
+        Ok(report)
+    }
+
     // pub async fn get_types(&self) -> Result<Vec<TypeView>, Error> {
     //     let types = self.assets.get_types().await?;
     //     Ok(types
@@ -81,47 +281,325 @@ impl FinanceService {
     //         .collect())
     // }
 
-    pub async fn add_type(&self, name: String, description: String) -> Result<(), Error> {
-        self.assets.add_type(name, description).await?;
+    pub async fn add_classification(
+        &self,
+        name: String,
+        description: String,
+    ) -> Result<Classification, Error> {
+        self.assets.add_classification(name, description).await
+    }
+
+    pub async fn remove_classification(&self, name: &str) -> Result<(), Error> {
+        let classification = self.find_classification(name).await?;
+        self.assets.remove_classification(&classification).await
+    }
+
+    async fn find_classification(&self, name: &str) -> Result<Classification, Error> {
+        self.assets
+            .classification(name)
+            .await?
+            .ok_or(Error::msg("Classification not found"))
+    }
+
+    /// Adds the type to the classification of the parent, the given classification or the default one.
+    pub async fn add_type(
+        &self,
+        name: String,
+        description: String,
+        parent: Option<String>,
+        classification: Option<String>,
+    ) -> Result<(), Error> {
+        let parent = match parent {
+            Some(parent) => Some(self.find_type(&parent).await?),
+            None => None,
+        };
+        let classification = match (classification, &parent) {
+            (Some(classification), _) => self.find_classification(&classification).await?,
+            (None, Some(parent)) => self
+                .assets
+                .classifications()
+                .await?
+                .into_iter()
+                .find(|c| c.id == parent.classification)
+                .ok_or(Error::msg("Classification not found"))?,
+            (None, None) => self.assets.default_classification().await?,
+        };
+        self.assets
+            .add_type(name, description, parent.as_ref(), &classification)
+            .await?;
         Ok(())
     }
 
-    pub async fn remove_type(&self, id: String) -> Result<(), Error> {
-        let tp = self
+    /// Moves the type under another parent, or makes it a root.
+    pub async fn set_type_parent(&self, name: String, parent: Option<String>) -> Result<(), Error> {
+        let tp = self.find_type(&name).await?;
+        let parent = match parent {
+            Some(parent) => Some(self.find_type(&parent).await?),
+            None => None,
+        };
+        self.assets.set_type_parent(&tp, parent.as_ref()).await
+    }
+
+    pub async fn type_trees(&self) -> Result<Vec<ClassificationTree>, Error> {
+        self.assets.type_trees().await
+    }
+
+    pub async fn remove_type(&self, id: String, children: ChildTypes) -> Result<(), Error> {
+        let tp = self.find_type(&id).await?;
+        self.assets.remove_type(tp, children).await?;
+        Ok(())
+    }
+
+    /// Finds a type by `name` or `classification:name`.
+    async fn find_type(&self, key: &str) -> Result<AssetType, Error> {
+        self.assets
+            .find_type(key)
+            .await?
+            .ok_or(Error::msg(format!("Type not found: {}", key)))
+    }
+
+    /// Portfolio breakdown by the classification in the base currency.
+    pub async fn breakdown(&self, classification: &str) -> Result<Breakdown, Error> {
+        let classification = self.find_classification(classification).await?;
+        let values = self.asset_values().await?;
+        let types = self.assets.get_types().await?;
+        let links = self.assets.get_type_links().await?;
+        Ok(breakdown(classification, &types, &links, &values))
+    }
+
+    /// Portfolio breakdowns by every classification.
+    pub async fn breakdowns(&self) -> Result<Vec<Breakdown>, Error> {
+        let values = self.asset_values().await?;
+        let types = self.assets.get_types().await?;
+        let links = self.assets.get_type_links().await?;
+        Ok(self
             .assets
-            .get_type_by_name(id)
+            .classifications()
             .await?
-            .ok_or(Error::msg("Type not found"))?;
-        self.assets.remove_type(tp).await?;
-        Ok(())
+            .into_iter()
+            .map(|classification| breakdown(classification, &types, &links, &values))
+            .collect())
     }
 
-    pub async fn add_asset_type(&self, asset: String, tp: String) -> Result<(), Error> {
-        let asset = self
+    /// Sets the target share of the type in percent. The band defaults to 5 percentage points.
+    pub async fn set_target(
+        &self,
+        tp: &str,
+        weight: Decimal,
+        band: Option<Decimal>,
+    ) -> Result<(), Error> {
+        let tp = self.find_type(tp).await?;
+        let types = self.assets.get_types().await?;
+        let band = band.unwrap_or(Decimal::int(DEFAULT_DRIFT_BAND));
+        self.target.set(&tp, weight, band, &types).await
+    }
+
+    pub async fn remove_target(&self, tp: &str) -> Result<(), Error> {
+        let tp = self.find_type(tp).await?;
+        self.target.remove(&tp).await
+    }
+
+    /// Current against target allocation of the classification types.
+    pub async fn allocation_drift(&self, classification: &str) -> Result<Vec<TargetDrift>, Error> {
+        let breakdown = self.breakdown(classification).await?;
+        Ok(drift(&breakdown, &self.target.list().await?))
+    }
+
+    /// Targets of every classification with the drift outside of the band.
+    pub async fn drift_alerts(&self) -> Result<Vec<TargetDrift>, Error> {
+        let targets = self.target.list().await?;
+        Ok(self
+            .breakdowns()
+            .await?
+            .iter()
+            .flat_map(|breakdown| drift(breakdown, &targets))
+            .filter(|drift| drift.out_of_band)
+            .collect())
+    }
+
+    /// Proposes Buy and Sell operations bringing the account holdings to the classification targets.
+    pub async fn rebalance(
+        &self,
+        account: &str,
+        classification: &str,
+        options: &RebalanceOptions,
+    ) -> Result<RebalancePlan, Error> {
+        let account = self
+            .account
+            .account(account)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
+        let classification = self.find_classification(classification).await?;
+        let types = self
             .assets
-            .get_asset_by_ticker(asset)
+            .get_types()
             .await?
-            .ok_or(Error::msg("Asset not found"))?;
-        let tp = self
+            .into_iter()
+            .filter(|t| t.classification == classification.id)
+            .collect::<Vec<_>>();
+        let links = self.assets.get_type_links().await?;
+        let base = self.base_currency().await?;
+        let base_rate = self.currency_rate(base.id).await?;
+
+        let AccountStatistic {
+            account,
+            mut assets,
+            cash: balances,
+            ..
+        } = self.build_account_statistic(account).await?;
+        let mut cash = HashMap::<i64, Decimal>::new();
+        for balance in balances {
+            let rate = self.currency_rate(balance.currency).await?;
+            *cash.entry(balance.currency).or_default() += balance.balance * rate / base_rate;
+        }
+        // Classified assets which are not held can be bought too.
+        for link in &links {
+            if !types.iter().any(|t| t.id == link.type_id)
+                || assets.iter().any(|(a, _)| a.id == link.asset_id)
+            {
+                continue;
+            }
+            if let Some(asset) = self.assets.get_asset(link.asset_id).await? {
+                let stat = self
+                    .account_asset_statistic(asset.clone(), Some(&account))
+                    .await?;
+                let snapshot = stat.get_last_snapshot().cloned().unwrap_or_default();
+                assets.push((asset, snapshot));
+            }
+        }
+        let mut holdings = Vec::new();
+        for (asset, snapshot) in assets {
+            let rate = self.currency_rate(asset.currency).await?;
+            holdings.push(Holding {
+                asset,
+                quantity: snapshot.quantity,
+                price: snapshot.price * rate / base_rate,
+            });
+        }
+
+        let targets = self.target.list().await?;
+        Ok(rebalance(
+            &types,
+            &links,
+            &targets,
+            &holdings,
+            &cash,
+            account.currency,
+            options,
+        ))
+    }
+
+    /// Adds the proposed operations to the account. The whole plan is validated first and
+    /// written in one transaction, nothing is written if any operation is rejected or a purchase
+    /// takes more cash than the account holds in the asset currency.
+    pub async fn commit_rebalance(
+        &self,
+        account: &str,
+        plan: &RebalancePlan,
+    ) -> Result<Vec<Violation>, Error> {
+        let account = self
+            .account
+            .account(account)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
+        let date = Utc::now().naive_utc();
+        let mut cash = HashMap::<i64, Decimal>::new();
+        for balance in self.cash.balances().await? {
+            if balance.account == account.id {
+                cash.insert(balance.currency, balance.balance);
+            }
+        }
+        let mut warnings = Vec::new();
+        let mut operations = Vec::new();
+        let mut pending = Vec::new();
+        for op in &plan.operations {
+            let (operation, price, violations) = self
+                .prepare_operation(
+                    &account,
+                    op.asset.clone(),
+                    op.amount,
+                    op.operation_type,
+                    date,
+                    &pending,
+                )
+                .await?;
+            let flow = cash_flow(op.operation_type, op.amount, price);
+            let budget = cash.entry(op.asset.currency).or_default();
+            *budget += flow;
+            if flow < Decimal::default() && *budget < Decimal::default() {
+                return Err(Error::msg(format!(
+                    "Not enough cash to {} {} of {}",
+                    String::from(op.operation_type),
+                    op.amount,
+                    op.asset.ticker
+                )));
+            }
+            warnings.extend(violations);
+            pending.push(operation.clone());
+            operations.push((operation, price));
+        }
+        self.assets.add_operations(&operations).await?;
+        Ok(warnings)
+    }
+
+    /// Value of every held asset across all accounts in the base currency.
+    async fn asset_values(&self) -> Result<HashMap<i64, Decimal>, Error> {
+        let base = self.base_currency().await?;
+        let base_rate = self.currency_rate(base.id).await?;
+        let mut values = HashMap::new();
+        for asset in self.assets.get_assets().await? {
+            let rate = self.currency_rate(asset.currency).await?;
+            let id = asset.id;
+            let stat = self.asset_statistic(asset).await?;
+            if let Some(snapshot) = stat.get_last_snapshot() {
+                let mut totals = Totals::default();
+                totals.add_snapshot(snapshot, rate, base_rate)?;
+                values.insert(id, totals.value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Links the asset to the type, the weight defaults to 100%.
+    pub async fn add_asset_type(
+        &self,
+        asset: String,
+        tp: String,
+        weight: Option<Decimal>,
+    ) -> Result<(), Error> {
+        let asset = self
             .assets
-            .get_type_by_name(tp)
+            .find_asset(&asset)
             .await?
-            .ok_or(Error::msg("Type not found"))?;
-        self.assets.add_asset_type(&asset, &tp).await?;
+            .ok_or(Error::msg("Asset not found"))?;
+        let tp = self.find_type(&tp).await?;
+        self.assets
+            .add_asset_type(&asset, &tp, weight.unwrap_or(Decimal::int(100)))
+            .await?;
         Ok(())
     }
 
-    pub async fn remove_asset_type(&self, asset: String, tp: String) -> Result<(), Error> {
+    /// Imports the fund composition from a `type,weight` csv.
+    pub async fn import_composition(&self, asset: String, csv: &str) -> Result<(), Error> {
         let asset = self
             .assets
-            .get_asset_by_ticker(asset)
+            .find_asset(&asset)
             .await?
             .ok_or(Error::msg("Asset not found"))?;
-        let tp = self
+        let mut composition = Vec::new();
+        for row in composition::parse_csv(csv)? {
+            composition.push((self.find_type(&row.tp).await?, row.weight));
+        }
+        self.assets.set_composition(&asset, &composition).await
+    }
+
+    pub async fn remove_asset_type(&self, asset: String, tp: String) -> Result<(), Error> {
+        let asset = self
             .assets
-            .get_type_by_name(tp)
+            .find_asset(&asset)
             .await?
-            .ok_or(Error::msg("Type not found"))?;
+            .ok_or(Error::msg("Asset not found"))?;
+        let tp = self.find_type(&tp).await?;
         self.assets.remove_asset_type(&asset, &tp).await?;
         Ok(())
     }
@@ -145,6 +623,7 @@ impl FinanceService {
         name: Option<String>,
         description: Option<String>,
         currency: String,
+        ids: AssetIdentifiers,
     ) -> Result<Asset, Error> {
         let currency = self
             .currency
@@ -155,27 +634,129 @@ impl FinanceService {
         let description = description.unwrap_or_default();
         let asset = self
             .assets
-            .add_asset(ticker.clone(), name, description, &currency)
+            .add_asset(ticker.clone(), name, description, &currency, ids)
             .await?;
         Ok(asset)
     }
 
+    /// Finds an asset by ticker, `TICKER@MIC`, `isin:`, `figi:` or `cusip:` identifier.
+    pub async fn find_asset(&self, key: &str) -> Result<Option<Asset>, Error> {
+        self.assets.find_asset(key).await
+    }
+
+    pub async fn set_asset_identifiers(
+        &self,
+        key: String,
+        ids: AssetIdentifiers,
+    ) -> Result<Asset, Error> {
+        let asset = self
+            .assets
+            .find_asset(&key)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        self.assets.set_identifiers(&asset, ids).await
+    }
+
+    pub async fn set_lot_size(&self, key: String, lot_size: Decimal) -> Result<(), Error> {
+        let asset = self
+            .assets
+            .find_asset(&key)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        self.assets.set_lot_size(&asset, lot_size).await
+    }
+
+    /// Sets the currency the asset value depends on, `None` resets it to the trading currency.
+    pub async fn set_exposure_currency(
+        &self,
+        key: String,
+        currency: Option<String>,
+    ) -> Result<(), Error> {
+        let asset = self
+            .assets
+            .find_asset(&key)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        let currency = match currency {
+            Some(ticker) => Some(
+                self.currency
+                    .currency(&ticker)
+                    .await?
+                    .ok_or(Error::msg("Currency not found"))?,
+            ),
+            None => None,
+        };
+        self.assets
+            .set_exposure_currency(&asset, currency.as_ref())
+            .await
+    }
+
     pub async fn remove_asset(&self, ticker: String) -> Result<(), Error> {
-        self.assets.remove_asset(ticker).await?;
+        self.assets.remove_asset(&ticker).await?;
         Ok(())
     }
 
+    /// Adds an operation to the default account.
     pub async fn add_operation(
         &self,
         asset: String,
         amount: Decimal,
         tp: OperationType,
-    ) -> Result<(), Error> {
+    ) -> Result<Vec<Violation>, Error> {
+        self.add_operation_on(DEFAULT_ACCOUNT, asset, amount, tp, Utc::now().naive_utc())
+            .await
+    }
+
+    /// Adds a dated operation linked to the currency rate in effect at its date.
+    /// The operation is validated against the account history, returns validation warnings.
+    pub async fn add_operation_on(
+        &self,
+        account: &str,
+        asset: String,
+        amount: Decimal,
+        tp: OperationType,
+        date: NaiveDateTime,
+    ) -> Result<Vec<Violation>, Error> {
+        let account = self
+            .account
+            .account(account)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
         let asset = self
             .assets
-            .get_asset_by_ticker(asset)
+            .find_asset(&asset)
             .await?
             .ok_or(Error::msg("Asset not found"))?;
+        self.record_operation(&account, asset, amount, tp, date)
+            .await
+    }
+
+    async fn record_operation(
+        &self,
+        account: &Account,
+        asset: Asset,
+        amount: Decimal,
+        tp: OperationType,
+        date: NaiveDateTime,
+    ) -> Result<Vec<Violation>, Error> {
+        let (operation, price, warnings) = self
+            .prepare_operation(account, asset, amount, tp, date, &[])
+            .await?;
+        self.assets.add_operations(&[(operation, price)]).await?;
+        Ok(warnings)
+    }
+
+    /// Links the operation to the currency rate of its date and validates it against the account
+    /// history followed by the pending operations. Returns it with the asset price and warnings.
+    async fn prepare_operation(
+        &self,
+        account: &Account,
+        asset: Asset,
+        amount: Decimal,
+        tp: OperationType,
+        date: NaiveDateTime,
+        pending: &[AssetOperation],
+    ) -> Result<(AssetOperation, Decimal, Vec<Violation>), Error> {
         let currency = self
             .currency
             .currency_by_id(asset.currency)
@@ -183,24 +764,1020 @@ impl FinanceService {
             .ok_or(Error::msg("Currency not found for asset"))?;
         let rate = self
             .currency
-            .last_rate(&currency)
+            .rate_at(&currency, date)
+            .await?
+            .ok_or(Error::msg(format!(
+                "Operation date {} is before the first {} rate",
+                date, currency.ticker
+            )))?;
+
+        let operation = AssetOperation {
+            id: 0,
+            asset_id: asset.id,
+            operation_type: tp,
+            operation_date: date,
+            operation_amount: amount,
+            currency_rate: rate.id,
+            account: account.id,
+        };
+        let mut ops = self
+            .assets
+            .get_account_operations(&asset, Some(account))
+            .await?;
+        ops.extend(pending.iter().filter(|op| op.asset_id == asset.id).cloned());
+        let changes = self
+            .position_changes(&asset, Some(account), Vec::new())
+            .await?;
+        let history = AssetStatistic::with_changes(asset.clone(), ops.clone(), changes.clone());
+        let mut replayed = ops;
+        replayed.push(operation.clone());
+        let replayed = AssetStatistic::with_changes(asset, replayed, changes);
+        let warnings =
+            self.validation
+                .check(&operation, Some(&account.name), &history, &replayed)?;
+
+        let price = history
+            .get_latest_snapshot(date)
+            .map(|s| s.price)
+            .unwrap_or_default();
+        Ok((operation, price, warnings))
+    }
+
+    /// Renames the asset. Operations stay with it.
+    pub async fn change_ticker(
+        &self,
+        ticker: String,
+        new_ticker: String,
+        new_name: Option<String>,
+        date: NaiveDateTime,
+    ) -> Result<Asset, Error> {
+        let asset = self
+            .assets
+            .find_asset(&ticker)
             .await?
-            .ok_or(Error::msg("Currency rate not found for asset currency"))?;
-        self.assets.add_operation(&asset, &rate, tp, amount).await?;
+            .ok_or(Error::msg("Asset not found"))?;
+        let name = new_name.unwrap_or(asset.name.clone());
+        self.assets
+            .change_ticker(&asset, new_ticker, name, date)
+            .await
+    }
+
+    /// Converts the position in `ticker` into `target` shares at `ratio` per share.
+    pub async fn merge_asset(
+        &self,
+        ticker: String,
+        target: String,
+        ratio: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let (asset, target) = self.asset_pair(ticker, target).await?;
+        self.assets.merge(&asset, &target, ratio, date).await?;
+        Ok(())
+    }
+
+    /// Receives `ratio` shares of `target` per share of `ticker`
+    /// and moves the `cost_basis` share of the cost basis to it.
+    pub async fn spin_off_asset(
+        &self,
+        ticker: String,
+        target: String,
+        ratio: Decimal,
+        cost_basis: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let (asset, target) = self.asset_pair(ticker, target).await?;
+        self.assets
+            .spin_off(&asset, &target, ratio, cost_basis, date)
+            .await?;
         Ok(())
     }
 
+    async fn asset_pair(&self, ticker: String, target: String) -> Result<(Asset, Asset), Error> {
+        let asset = self
+            .assets
+            .find_asset(&ticker)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        let target = self
+            .assets
+            .find_asset(&target)
+            .await?
+            .ok_or(Error::msg("Target asset not found"))?;
+        Ok((asset, target))
+    }
+
+    /// Asset statistic across all accounts,
+    /// including positions received through mergers and spin-offs.
+    pub async fn asset_statistic(&self, asset: Asset) -> Result<AssetStatistic, Error> {
+        self.account_asset_statistic(asset, None).await
+    }
+
+    /// Asset statistic within the account, or across all accounts if it is not set.
+    pub async fn account_asset_statistic(
+        &self,
+        asset: Asset,
+        account: Option<&Account>,
+    ) -> Result<AssetStatistic, Error> {
+        let ops = self.assets.get_account_operations(&asset, account).await?;
+        let changes = self.position_changes(&asset, account, Vec::new()).await?;
+        Ok(AssetStatistic::with_changes(asset, ops, changes))
+    }
+
+    /// Corporate actions of the asset, following the chain of source assets.
+    fn position_changes<'a>(
+        &'a self,
+        asset: &'a Asset,
+        account: Option<&'a Account>,
+        mut chain: Vec<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<PositionChange>, Error>> + Send + 'a>> {
+        Box::pin(async move {
+            if chain.contains(&asset.id) {
+                return Err(Error::msg(format!(
+                    "Corporate actions of {} form a cycle",
+                    asset.ticker
+                )));
+            }
+            chain.push(asset.id);
+
+            let mut changes = Vec::new();
+            for action in self.assets.get_corporate_actions(asset).await? {
+                let effect = match action.action_type {
+                    CorporateActionType::TickerChange => continue,
+                    CorporateActionType::Merger if action.asset_id == asset.id => ActionEffect::Out,
+                    CorporateActionType::SpinOff if action.asset_id == asset.id => {
+                        ActionEffect::CostOut(action.cost_basis)
+                    }
+                    tp => {
+                        let source = self
+                            .assets
+                            .get_asset(action.asset_id)
+                            .await?
+                            .ok_or(Error::msg("Asset not found"))?;
+                        let ops = self.assets.get_account_operations(&source, account).await?;
+                        let source_changes = self
+                            .position_changes(&source, account, chain.clone())
+                            .await?;
+                        let source = AssetStatistic::with_changes(source, ops, source_changes);
+                        let before = source.before_action(action.id).cloned().unwrap_or_default();
+                        ActionEffect::In(if tp == CorporateActionType::Merger {
+                            before.merged(action.ratio)
+                        } else {
+                            before.spun_off(action.ratio, action.cost_basis)
+                        })
+                    }
+                };
+                changes.push(PositionChange {
+                    action_id: action.id,
+                    date: action.action_date,
+                    effect,
+                });
+            }
+            Ok(changes)
+        })
+    }
+
     pub async fn asset_info(&self, ticker: String) -> Result<AssetSnapshot, Error> {
         let asset = self
             .assets
-            .get_asset_by_ticker(ticker)
+            .find_asset(&ticker)
             .await?
             .ok_or(Error::msg("Asset not found"))?;
-        let ops = self.assets.get_operations(&asset).await?;
-        let stat = AssetStatistic::new(asset, ops);
+        let stat = self.asset_statistic(asset).await?;
         Ok(stat
             .get_last_snapshot()
             .ok_or(Error::msg("Asset statistic not found for asset"))?
             .clone())
     }
+
+    /// Last snapshot of the asset in the base currency.
+    pub async fn asset_base_info(&self, ticker: String) -> Result<BaseSnapshot, Error> {
+        let asset = self
+            .assets
+            .find_asset(&ticker)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        let stat = self.asset_statistic(asset).await?;
+        self.base_snapshots(&stat)
+            .await?
+            .pop()
+            .ok_or(Error::msg("Asset statistic not found for asset"))
+    }
+
+    /// Asset snapshots converted to the base currency.
+    pub async fn base_snapshots(&self, stat: &AssetStatistic) -> Result<Vec<BaseSnapshot>, Error> {
+        let (rates, history) = self.asset_rates(&stat.asset).await?;
+        Ok(stat.base_snapshots(&rates, &history))
+    }
+
+    /// Rates recorded on the asset operations and the asset currency rate history.
+    async fn asset_rates(
+        &self,
+        asset: &Asset,
+    ) -> Result<(HashMap<i64, Rate>, CurrencyStatistic), Error> {
+        let currency = self
+            .currency
+            .currency_by_id(asset.currency)
+            .await?
+            .ok_or(Error::msg("Currency not found for asset"))?;
+        let rates = self
+            .assets
+            .get_operation_rates(asset)
+            .await?
+            .into_iter()
+            .map(|r| (r.id, r.rate))
+            .collect::<HashMap<_, _>>();
+        let history = self.currency.rates(&currency).await?;
+        Ok((rates, CurrencyStatistic::new(currency, history)))
+    }
+
+    /// Explains the change of the asset value between the dates in the base currency.
+    pub async fn asset_attribution(
+        &self,
+        ticker: String,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Result<Attribution, Error> {
+        let asset = self
+            .assets
+            .find_asset(&ticker)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        let stat = self.asset_statistic(asset).await?;
+        let (rates, history) = self.asset_rates(&stat.asset).await?;
+        Ok(attribution(&stat, &rates, &history, from, to))
+    }
+
+    /// Explains the change of the portfolio value between the dates per asset and per type
+    /// of the classification.
+    pub async fn attribution_report(
+        &self,
+        classification: &str,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Result<AttributionReport, Error> {
+        let classification = self.find_classification(classification).await?;
+        let mut assets = Vec::new();
+        let mut total = Attribution::default();
+        for asset in self.assets.get_assets().await? {
+            let stat = self.asset_statistic(asset).await?;
+            let (rates, history) = self.asset_rates(&stat.asset).await?;
+            let attribution = attribution(&stat, &rates, &history, from, to);
+            total += attribution.clone();
+            assets.push((stat.asset, attribution));
+        }
+        let types = self
+            .assets
+            .get_types()
+            .await?
+            .into_iter()
+            .filter(|t| t.classification == classification.id)
+            .collect::<Vec<_>>();
+        let links = self.assets.get_type_links().await?;
+        let by_asset = assets
+            .iter()
+            .map(|(asset, attribution)| (asset.id, attribution.clone()))
+            .collect::<HashMap<_, _>>();
+        Ok(AttributionReport {
+            types: by_type(&types, &links, &by_asset),
+            assets,
+            total,
+        })
+    }
+
+    /// Gain, time-weighted return and income for the periods per asset, per type of the
+    /// classification and of the whole portfolio, see [Period::standard].
+    pub async fn performance_table(
+        &self,
+        classification: &str,
+        periods: &[Period],
+    ) -> Result<PerformanceTable, Error> {
+        let classification = self.find_classification(classification).await?;
+        let now = Utc::now().naive_utc();
+        let histories = self.asset_histories().await?;
+        let row = |assets: &[(&AssetHistory, Decimal)]| {
+            periods
+                .iter()
+                .map(|period| performance(assets, *period, now))
+                .collect::<Vec<_>>()
+        };
+
+        let types = self
+            .assets
+            .get_types()
+            .await?
+            .into_iter()
+            .filter(|t| t.classification == classification.id)
+            .collect::<Vec<_>>();
+        let links = self.assets.get_type_links().await?;
+        let by_type = types
+            .iter()
+            .map(|tp| {
+                let weights = type_weights(&subtree(&types, tp.id), &links);
+                let members = histories
+                    .iter()
+                    .filter_map(|h| weights.get(&h.stat.asset.id).map(|w| (h, *w)))
+                    .collect::<Vec<_>>();
+                (tp.clone(), row(&members))
+            })
+            .collect();
+        let all = histories
+            .iter()
+            .map(|h| (h, Decimal::int(100)))
+            .collect::<Vec<_>>();
+        Ok(PerformanceTable {
+            periods: periods.to_vec(),
+            assets: all
+                .iter()
+                .map(|member| (member.0.stat.asset.clone(), row(&[*member])))
+                .collect(),
+            types: by_type,
+            total: row(&all),
+        })
+    }
+
+    /// Histories of all assets with snapshots.
+    async fn asset_histories(&self) -> Result<Vec<AssetHistory>, Error> {
+        let mut histories = Vec::new();
+        for asset in self.assets.get_assets().await? {
+            let history = self.asset_history(asset).await?;
+            if !history.stat.snapshots().is_empty() {
+                histories.push(history);
+            }
+        }
+        Ok(histories)
+    }
+
+    async fn asset_history(&self, asset: Asset) -> Result<AssetHistory, Error> {
+        let stat = self.asset_statistic(asset).await?;
+        let (rates, currency) = self.asset_rates(&stat.asset).await?;
+        let base = stat.base_snapshots(&rates, &currency);
+        Ok(AssetHistory::new(stat, base, currency))
+    }
+
+    /// Risk metrics of the asset value sampled every `step` between the dates.
+    pub async fn asset_risk(
+        &self,
+        ticker: String,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+        options: &RiskOptions,
+    ) -> Result<RiskMetrics, Error> {
+        let asset = self
+            .assets
+            .find_asset(&ticker)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        let history = self.asset_history(asset).await?;
+        let series = value_series(&[(&history, Decimal::int(100))], from, to, step)?;
+        self.risk_metrics(&series, from, to, step, options).await
+    }
+
+    /// Risk metrics of the whole portfolio value sampled every `step` between the dates.
+    pub async fn portfolio_risk(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+        options: &RiskOptions,
+    ) -> Result<RiskMetrics, Error> {
+        let histories = self.asset_histories().await?;
+        let all = histories
+            .iter()
+            .map(|h| (h, Decimal::int(100)))
+            .collect::<Vec<_>>();
+        let series = value_series(&all, from, to, step)?;
+        self.risk_metrics(&series, from, to, step, options).await
+    }
+
+    async fn risk_metrics(
+        &self,
+        series: &[(NaiveDateTime, Valuation)],
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+        options: &RiskOptions,
+    ) -> Result<RiskMetrics, Error> {
+        let periods_per_year = periods_per_year(step);
+        let own = returns(series);
+        let risk_free = match &options.risk_free {
+            RiskFree::Fixed(rate) => vec![periodic_rate(*rate, periods_per_year); own.len()],
+            RiskFree::Series(ticker) => {
+                let currency = self
+                    .currency
+                    .currency(ticker)
+                    .await?
+                    .ok_or(Error::msg("Risk-free rate series not found"))?;
+                let rates = self.currency.rates(&currency).await?;
+                let history = CurrencyStatistic::new(currency, rates);
+                own.iter()
+                    .map(|(date, _)| {
+                        periodic_rate(
+                            history.get_rate(*date).unwrap_or_default().to_decimal(),
+                            periods_per_year,
+                        )
+                    })
+                    .collect()
+            }
+        };
+        let benchmark = match &options.benchmark {
+            Some(ticker) => {
+                let asset = self
+                    .assets
+                    .find_asset(ticker)
+                    .await?
+                    .ok_or(Error::msg("Benchmark asset not found"))?;
+                let history = self.asset_history(asset).await?;
+                Some(returns(&value_series(
+                    &[(&history, Decimal::int(100))],
+                    from,
+                    to,
+                    step,
+                )?))
+            }
+            None => None,
+        };
+        Ok(risk(
+            &own,
+            &risk_free,
+            benchmark.as_deref(),
+            periods_per_year,
+        ))
+    }
+
+    /// Compares the asset, or the whole portfolio if `ticker` is not set, with the benchmark
+    /// asset bought with the same cash flows.
+    pub async fn compare_with_benchmark(
+        &self,
+        ticker: Option<String>,
+        benchmark: String,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+    ) -> Result<Comparison, Error> {
+        let benchmark = self
+            .assets
+            .find_asset(&benchmark)
+            .await?
+            .ok_or(Error::msg("Benchmark asset not found"))?;
+        let benchmark = self.asset_history(benchmark).await?;
+        let histories = match ticker {
+            Some(ticker) => {
+                let asset = self
+                    .assets
+                    .find_asset(&ticker)
+                    .await?
+                    .ok_or(Error::msg("Asset not found"))?;
+                vec![self.asset_history(asset).await?]
+            }
+            None => self
+                .asset_histories()
+                .await?
+                .into_iter()
+                .filter(|h| h.stat.asset.id != benchmark.stat.asset.id)
+                .collect(),
+        };
+        let subject = histories
+            .iter()
+            .map(|h| (h, Decimal::int(100)))
+            .collect::<Vec<_>>();
+        compare(&subject, &benchmark, from, to, step)
+    }
+
+    /// Correlation of the asset price and currency rate returns sampled every `step`
+    /// between the dates. Pairs with fewer than `min_overlap` common returns are not compared.
+    pub async fn correlation_matrix(
+        &self,
+        assets: &[String],
+        currencies: &[String],
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+        min_overlap: usize,
+    ) -> Result<CorrelationMatrix, Error> {
+        let dates = sample_dates(from, to, step)?;
+
+        let mut series = Vec::new();
+        for ticker in assets {
+            let asset = self
+                .assets
+                .find_asset(ticker)
+                .await?
+                .ok_or(Error::msg(format!("Asset {} not found", ticker)))?;
+            let stat = self.asset_statistic(asset).await?;
+            series.push(AlignedSeries {
+                name: stat.asset.ticker.clone(),
+                values: dates
+                    .iter()
+                    .map(|date| stat.get_latest_snapshot(*date).map(|s| f64::from(s.price)))
+                    .collect(),
+            });
+        }
+        for ticker in currencies {
+            let currency = self
+                .currency
+                .currency(ticker)
+                .await?
+                .ok_or(Error::msg(format!("Currency {} not found", ticker)))?;
+            let rates = self.currency.rates(&currency).await?;
+            let name = currency.ticker.clone();
+            let history = CurrencyStatistic::new(currency, rates);
+            series.push(AlignedSeries {
+                name,
+                values: dates
+                    .iter()
+                    .map(|date| history.get_rate(*date).map(f64::from))
+                    .collect(),
+            });
+        }
+        Ok(correlation(&series, min_overlap))
+    }
+
+    /// Rate graph of the currency with the overlays.
+    pub async fn currency_graph(
+        &self,
+        ticker: &str,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+        overlays: &[Overlay],
+    ) -> Result<OverlayGraph, Error> {
+        let dates = sample_dates(from, to, step)?;
+        if overlays.iter().any(|o| o.window() == 0) {
+            return Err(Error::msg("Overlay window must be positive"));
+        }
+        let currency = self
+            .currency
+            .currency(ticker)
+            .await?
+            .ok_or(Error::msg("Currency not found"))?;
+        let rates = self.currency.rates(&currency).await?;
+        let history = CurrencyStatistic::new(currency, rates);
+        Ok(with_overlays(history.build_graph(&dates, step), overlays))
+    }
+
+    /// Price graph of the asset in its currency with the overlays.
+    pub async fn price_graph(
+        &self,
+        ticker: String,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+        overlays: &[Overlay],
+    ) -> Result<OverlayGraph, Error> {
+        let dates = sample_dates(from, to, step)?;
+        if overlays.iter().any(|o| o.window() == 0) {
+            return Err(Error::msg("Overlay window must be positive"));
+        }
+        let asset = self
+            .assets
+            .find_asset(&ticker)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        let stat = self.asset_statistic(asset).await?;
+        Ok(with_overlays(stat.price_graph(&dates), overlays))
+    }
+
+    /// Current value of the assets and cash per exposure currency.
+    /// `shock` is the currency move in percent, 10% by default.
+    pub async fn currency_exposure(&self, shock: Option<Decimal>) -> Result<ExposureReport, Error> {
+        let now = Utc::now().naive_utc();
+        self.exposure_reports(&[now], shock.unwrap_or(Decimal::int(10)))
+            .await?
+            .pop()
+            .ok_or(Error::msg("Exposure not found"))
+    }
+
+    /// Currency exposure at every step from `from` to `to`.
+    pub async fn exposure_history(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        step: Duration,
+        shock: Option<Decimal>,
+    ) -> Result<Vec<ExposureReport>, Error> {
+        let dates = sample_dates(from, to, step)?;
+        self.exposure_reports(&dates, shock.unwrap_or(Decimal::int(10)))
+            .await
+    }
+
+    /// Positions in a currency without a rate at the date are valued at zero.
+    async fn exposure_reports(
+        &self,
+        dates: &[NaiveDateTime],
+        shock: Decimal,
+    ) -> Result<Vec<ExposureReport>, Error> {
+        let base = self.base_currency().await?;
+        let mut tickers = HashMap::new();
+        let mut rates = HashMap::new();
+        for currency in self.currency.list().await? {
+            for date in dates {
+                if let Some(rate) = self.currency.rate_value_at(&currency, *date).await? {
+                    rates.insert((currency.id, *date), rate);
+                }
+            }
+            tickers.insert(currency.id, currency.ticker);
+        }
+        let rate = |currency: i64, date: NaiveDateTime| {
+            rates.get(&(currency, date)).copied().unwrap_or_default()
+        };
+
+        let mut stats = Vec::new();
+        for asset in self.assets.get_assets().await? {
+            stats.push(self.asset_statistic(asset).await?);
+        }
+        let mut cash = Vec::new();
+        for account in self.account.list().await? {
+            cash.extend(self.cash.operations(&account).await?);
+        }
+
+        Ok(dates
+            .iter()
+            .map(|date| {
+                let mut positions = Vec::new();
+                for stat in &stats {
+                    if let Some(snapshot) = stat.get_latest_snapshot(*date) {
+                        let asset = &stat.asset;
+                        positions.push((
+                            asset.exposure_currency.unwrap_or(asset.currency),
+                            snapshot.quantity * snapshot.price * rate(asset.currency, *date),
+                        ));
+                    }
+                }
+                let mut balances = HashMap::<i64, Decimal>::new();
+                for op in cash.iter().filter(|op| op.operation_date <= *date) {
+                    *balances.entry(op.currency).or_default() += op.amount;
+                }
+                for (currency, balance) in balances {
+                    positions.push((currency, balance * rate(currency, *date)));
+                }
+                exposure(*date, &positions, &tickers, base.id, shock)
+            })
+            .collect())
+    }
+
+    pub async fn create_account(
+        &self,
+        name: String,
+        broker: String,
+        currency: String,
+        account_type: AccountType,
+    ) -> Result<Account, Error> {
+        let currency = self
+            .currency
+            .currency(&currency)
+            .await?
+            .ok_or(Error::msg("Currency not found"))?;
+        self.account
+            .create(name, broker, &currency, account_type)
+            .await
+    }
+
+    pub async fn remove_account(&self, name: &str) -> Result<(), Error> {
+        let account = self
+            .account
+            .account(name)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
+        self.account.remove(&account).await
+    }
+
+    pub async fn account_asset_info(
+        &self,
+        account: &str,
+        ticker: String,
+    ) -> Result<AssetSnapshot, Error> {
+        let account = self
+            .account
+            .account(account)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
+        let asset = self
+            .assets
+            .find_asset(&ticker)
+            .await?
+            .ok_or(Error::msg("Asset not found"))?;
+        let stat = self.account_asset_statistic(asset, Some(&account)).await?;
+        Ok(stat
+            .get_last_snapshot()
+            .ok_or(Error::msg("Asset statistic not found for asset"))?
+            .clone())
+    }
+
+    /// Holdings of the account with totals in the account currency.
+    pub async fn account_statistic(&self, name: &str) -> Result<AccountStatistic, Error> {
+        let account = self
+            .account
+            .account(name)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
+        self.build_account_statistic(account).await
+    }
+
+    /// Statistic of every account with totals in the base currency.
+    pub async fn accounts_statistic(&self) -> Result<AccountsStatistic, Error> {
+        let base = self.base_currency().await?;
+        let base_rate = self.currency_rate(base.id).await?;
+        let mut totals = Totals::default();
+        let mut accounts = Vec::new();
+        for account in self.account.list().await? {
+            let stat = self.build_account_statistic(account).await?;
+            let rate = self.currency_rate(stat.account.currency).await?;
+            totals += stat.totals.convert(rate, base_rate)?;
+            accounts.push(stat);
+        }
+        Ok(AccountsStatistic { accounts, totals })
+    }
+
+    async fn build_account_statistic(&self, account: Account) -> Result<AccountStatistic, Error> {
+        // Positions received through corporate actions have no operations in the account.
+        let mut assets = self.assets.find_assets_in_account(&account).await?;
+        let mut idx = 0;
+        while idx < assets.len() {
+            for action in self.assets.get_corporate_actions(&assets[idx]).await? {
+                let target = match action.target_id {
+                    Some(target) if action.asset_id == assets[idx].id => target,
+                    _ => continue,
+                };
+                if assets.iter().all(|a| a.id != target) {
+                    if let Some(target) = self.assets.get_asset(target).await? {
+                        assets.push(target);
+                    }
+                }
+            }
+            idx += 1;
+        }
+
+        let account_rate = self.currency_rate(account.currency).await?;
+        let mut totals = Totals::default();
+        let mut snapshots = Vec::new();
+        for asset in assets {
+            let rate = self.currency_rate(asset.currency).await?;
+            let stat = self
+                .account_asset_statistic(asset.clone(), Some(&account))
+                .await?;
+            let snapshot = stat.get_last_snapshot().cloned().unwrap_or_default();
+            totals.add_snapshot(&snapshot, rate, account_rate)?;
+            snapshots.push((asset, snapshot));
+        }
+
+        let mut cash = Vec::new();
+        for balance in self.cash.balances().await? {
+            if balance.account != account.id {
+                continue;
+            }
+            let rate = self.currency_rate(balance.currency).await?;
+            totals.add_cash(balance.balance, rate, account_rate)?;
+            cash.push(balance);
+        }
+        for exchange in self.cash.exchanges(&account).await? {
+            let cost = self.fx_cost(&exchange).await?;
+            totals.add_fx(&cost, account_rate)?;
+        }
+        Ok(AccountStatistic {
+            account,
+            assets: snapshots,
+            cash,
+            totals,
+        })
+    }
+
+    async fn currency_rate(&self, currency_id: i64) -> Result<Rate, Error> {
+        self.currency_rate_at(currency_id, Utc::now().naive_utc())
+            .await
+    }
+
+    pub async fn deposit_cash(
+        &self,
+        account: &str,
+        currency: &str,
+        amount: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let (account, currency) = self.account_currency(account, currency).await?;
+        self.cash.deposit(&account, &currency, amount, date).await?;
+        Ok(())
+    }
+
+    pub async fn withdraw_cash(
+        &self,
+        account: &str,
+        currency: &str,
+        amount: Decimal,
+        date: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let (account, currency) = self.account_currency(account, currency).await?;
+        self.cash
+            .withdraw(&account, &currency, amount, date)
+            .await?;
+        Ok(())
+    }
+
+    /// Converts `from_amount` of one cash balance into `to_amount` of another.
+    /// The optional fee is paid from the balance of its currency.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn exchange_cash(
+        &self,
+        account: &str,
+        from: &str,
+        from_amount: Decimal,
+        to: &str,
+        to_amount: Decimal,
+        fee: Option<(Decimal, &str)>,
+        date: NaiveDateTime,
+    ) -> Result<(CashExchange, FxCost), Error> {
+        let (account, from) = self.account_currency(account, from).await?;
+        let to = self
+            .currency
+            .currency(to)
+            .await?
+            .ok_or(Error::msg("Currency not found"))?;
+        let fee = match fee {
+            Some((fee, currency)) => {
+                let currency = self
+                    .currency
+                    .currency(currency)
+                    .await?
+                    .ok_or(Error::msg("Fee currency not found"))?;
+                Some((fee, currency))
+            }
+            None => None,
+        };
+        let exchange = self
+            .cash
+            .exchange(
+                &account,
+                date,
+                &from,
+                from_amount,
+                &to,
+                to_amount,
+                fee.as_ref().map(|(fee, currency)| (*fee, currency)),
+            )
+            .await?;
+        let cost = self.fx_cost(&exchange).await?;
+        Ok((exchange, cost))
+    }
+
+    /// Currency exchanges of the account with their cost against reference rates.
+    pub async fn fx_costs(&self, account: &str) -> Result<Vec<(CashExchange, FxCost)>, Error> {
+        let account = self
+            .account
+            .account(account)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
+        let mut costs = Vec::new();
+        for exchange in self.cash.exchanges(&account).await? {
+            let cost = self.fx_cost(&exchange).await?;
+            costs.push((exchange, cost));
+        }
+        Ok(costs)
+    }
+
+    async fn fx_cost(&self, exchange: &CashExchange) -> Result<FxCost, Error> {
+        let date = exchange.exchange_date;
+        let from_rate = self.currency_rate_at(exchange.from_currency, date).await?;
+        let to_rate = self.currency_rate_at(exchange.to_currency, date).await?;
+        let fee_rate = match exchange.fee_currency {
+            Some(currency) => self.currency_rate_at(currency, date).await?,
+            None => Rate::default(),
+        };
+        fx_cost(exchange, from_rate, to_rate, fee_rate)
+    }
+
+    async fn currency_rate_at(&self, currency_id: i64, date: NaiveDateTime) -> Result<Rate, Error> {
+        let currency = self
+            .currency
+            .currency_by_id(currency_id)
+            .await?
+            .ok_or(Error::msg("Currency not found"))?;
+        self.currency
+            .rate_value_at(&currency, date)
+            .await?
+            .ok_or(Error::msg(format!(
+                "No {} rate at {}",
+                currency.ticker, date
+            )))
+    }
+
+    async fn account_currency(
+        &self,
+        account: &str,
+        currency: &str,
+    ) -> Result<(Account, Currency), Error> {
+        let account = self
+            .account
+            .account(account)
+            .await?
+            .ok_or(Error::msg("Account not found"))?;
+        let currency = self
+            .currency
+            .currency(currency)
+            .await?
+            .ok_or(Error::msg("Currency not found"))?;
+        Ok((account, currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::test_pool;
+    use crate::service::decimal::{dec, rate};
+    use crate::service::statistics::rebalance::DraftOperation;
+
+    #[tokio::test]
+    async fn test_base_currency_rate() {
+        let finance = FinanceService::new(test_pool().await);
+        let base = finance.base_currency().await.unwrap();
+        let date = Utc::now().naive_utc();
+        finance
+            .currency
+            .add_rate_on(&base, rate("2"), date, &RateSource::Manual)
+            .await
+            .unwrap();
+
+        // A stored base rate is ignored, and no history is needed before it.
+        assert_eq!(finance.currency_rate(base.id).await.unwrap(), Rate::int(1));
+        let before = date - chrono::Duration::days(1);
+        assert_eq!(
+            finance.currency_rate_at(base.id, before).await.unwrap(),
+            Rate::int(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_rebalance() {
+        let finance = FinanceService::new(test_pool().await);
+        let asset = finance
+            .add_asset(
+                "SAP".to_string(),
+                None,
+                None,
+                "USD".to_string(),
+                AssetIdentifiers::default(),
+            )
+            .await
+            .unwrap();
+        finance
+            .deposit_cash(DEFAULT_ACCOUNT, "USD", dec("50"), Utc::now().naive_utc())
+            .await
+            .unwrap();
+        finance
+            .add_operation("SAP".to_string(), dec("10"), OperationType::UpdatePrice)
+            .await
+            .unwrap();
+        finance
+            .add_operation("SAP".to_string(), dec("5"), OperationType::Buy)
+            .await
+            .unwrap();
+        let draft = |operation_type: OperationType, amount: &str| DraftOperation {
+            asset: asset.clone(),
+            operation_type,
+            amount: dec(amount),
+            value: dec(amount) * dec("10"),
+        };
+        let plan = |operations: Vec<DraftOperation>| RebalancePlan {
+            operations,
+            ..Default::default()
+        };
+
+        // The second sell exceeds the position left by the first one.
+        let rejected = plan(vec![
+            draft(OperationType::Sell, "3"),
+            draft(OperationType::Sell, "3"),
+        ]);
+        assert!(finance
+            .commit_rebalance(DEFAULT_ACCOUNT, &rejected)
+            .await
+            .is_err());
+        assert_eq!(
+            finance.assets.get_operations(&asset).await.unwrap().len(),
+            2
+        );
+
+        // The cash was spent on the first purchase.
+        let overdraft = plan(vec![draft(OperationType::Buy, "1")]);
+        assert!(finance
+            .commit_rebalance(DEFAULT_ACCOUNT, &overdraft)
+            .await
+            .is_err());
+        assert_eq!(
+            finance.assets.get_operations(&asset).await.unwrap().len(),
+            2
+        );
+
+        let accepted = plan(vec![
+            draft(OperationType::Sell, "3"),
+            draft(OperationType::Buy, "1"),
+        ]);
+        finance
+            .commit_rebalance(DEFAULT_ACCOUNT, &accepted)
+            .await
+            .unwrap();
+        let snapshot = finance.asset_info("SAP".to_string()).await.unwrap();
+        assert_eq!(snapshot.quantity, dec("3"));
+        let cash = finance.cash.balances().await.unwrap();
+        assert_eq!(cash[0].balance, dec("20"));
+    }
 }
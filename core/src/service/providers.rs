@@ -0,0 +1,55 @@
+use crate::dao::fetch_status::FetchStatusDao;
+use crate::dao::model::fetch_status::FetchStatus;
+use crate::service::decimal::{Decimal, Rate};
+use async_trait::async_trait;
+use color_eyre::eyre::Error;
+
+pub const CURRENCY_KIND: &str = "Currency";
+pub const ASSET_KIND: &str = "Asset";
+
+/// Source of currency rates expressed in the base currency.
+#[async_trait]
+pub trait RateProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn fetch_rate(&self, ticker: &str) -> Result<Rate, Error>;
+}
+
+/// Source of asset prices expressed in the asset currency.
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn fetch_price(&self, ticker: &str) -> Result<Decimal, Error>;
+}
+
+pub struct FetchStatusService {
+    dao: FetchStatusDao,
+}
+
+impl FetchStatusService {
+    pub fn new(dao: FetchStatusDao) -> Self {
+        Self { dao }
+    }
+
+    pub async fn list(&self) -> Result<Vec<FetchStatus>, Error> {
+        self.dao.list().await
+    }
+
+    pub async fn get(&self, kind: &str, ticker: &str) -> Result<Option<FetchStatus>, Error> {
+        self.dao.get(kind, ticker).await
+    }
+
+    pub async fn record(
+        &self,
+        kind: &str,
+        ticker: &str,
+        provider: &str,
+        attempts: u32,
+        error: Option<String>,
+    ) -> Result<(), Error> {
+        self.dao
+            .record(kind, ticker, provider, attempts as i64, error)
+            .await
+    }
+}
@@ -0,0 +1,121 @@
+use crate::dao::model::currency::CurrencyRate;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use std::collections::{BTreeMap, HashSet};
+
+/// How long rate history is kept at each resolution.
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// Rates newer than this are kept as is.
+    pub full: Duration,
+    /// Rates newer than this are reduced to daily closes, older ones to monthly closes.
+    pub daily: Duration,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CompactionReport {
+    pub currencies: Vec<CurrencyCompaction>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CurrencyCompaction {
+    pub ticker: String,
+    /// Removed rates.
+    pub removed: usize,
+    /// Oldest and newest removed rate dates.
+    pub range: Option<(NaiveDateTime, NaiveDateTime)>,
+    /// Rates kept only because operations reference them.
+    pub referenced: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Bucket {
+    Day(NaiveDate),
+    Month(i32, u32),
+}
+
+/// Selects current rates to remove. The last rate of each bucket is the close and is kept,
+/// as well as any rate from `referenced`.
+/// Returns ids to remove and the number of rates kept only because they are referenced.
+pub fn plan_compaction(
+    rates: &[CurrencyRate],
+    referenced: &HashSet<i64>,
+    now: NaiveDateTime,
+    policy: RetentionPolicy,
+) -> (Vec<i64>, usize) {
+    let mut buckets = BTreeMap::<Bucket, Vec<&CurrencyRate>>::new();
+    for rate in rates.iter().filter(|r| r.superseded_at.is_none()) {
+        let age = now - rate.date;
+        let bucket = if age < policy.full {
+            continue;
+        } else if age < policy.daily {
+            Bucket::Day(rate.date.date())
+        } else {
+            Bucket::Month(rate.date.year(), rate.date.month())
+        };
+        buckets.entry(bucket).or_default().push(rate);
+    }
+
+    let mut remove = Vec::new();
+    let mut kept_referenced = 0;
+    for (_, mut bucket) in buckets {
+        bucket.sort_by_key(|r| (r.date, r.id));
+        bucket.pop();
+        for rate in bucket {
+            if referenced.contains(&rate.id) {
+                kept_referenced += 1;
+            } else {
+                remove.push(rate.id);
+            }
+        }
+    }
+    remove.sort();
+    (remove, kept_referenced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::test_utils::{rate, time};
+
+    #[test]
+    fn test_plan_compaction() {
+        let rates = vec![
+            // monthly
+            rate(1, "2020-01-01 10:00:00", "1"),
+            rate(2, "2020-01-15 10:00:00", "1"),
+            rate(3, "2020-01-31 10:00:00", "1"),
+            rate(4, "2020-02-01 10:00:00", "1"),
+            // daily
+            rate(5, "2020-12-01 10:00:00", "1"),
+            rate(6, "2020-12-01 12:00:00", "1"),
+            rate(7, "2020-12-01 11:00:00", "1"),
+            rate(8, "2020-12-02 10:00:00", "1"),
+            // full
+            rate(9, "2020-12-30 10:00:00", "1"),
+            rate(10, "2020-12-30 11:00:00", "1"),
+        ];
+        let policy = RetentionPolicy {
+            full: Duration::days(7),
+            daily: Duration::days(90),
+        };
+        let referenced = HashSet::from([2]);
+        let (remove, referenced) =
+            plan_compaction(&rates, &referenced, time("2021-01-01 00:00:00"), policy);
+        assert_eq!(remove, vec![1, 5, 7]);
+        assert_eq!(referenced, 1);
+    }
+
+    #[test]
+    fn test_plan_compaction_skips_superseded() {
+        let mut old = rate(1, "2020-01-01 10:00:00", "1");
+        old.superseded_at = Some(time("2020-01-02 00:00:00"));
+        let rates = vec![old, rate(2, "2020-01-01 10:00:00", "1")];
+        let policy = RetentionPolicy {
+            full: Duration::days(1),
+            daily: Duration::days(2),
+        };
+        let (remove, _) =
+            plan_compaction(&rates, &HashSet::new(), time("2021-01-01 00:00:00"), policy);
+        assert!(remove.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::dao::model::account::Account;
+use crate::dao::model::assets::Asset;
+use crate::dao::model::cash::CashBalance;
+use crate::service::decimal::{Decimal, Rate};
+use crate::service::statistics::assets::AssetSnapshot;
+use crate::service::statistics::cash::FxCost;
+use color_eyre::eyre::Error;
+use std::fmt::Display;
+use std::ops::AddAssign;
+
+/// Position totals in one currency.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Totals {
+    /// Market value of held assets and cash.
+    pub value: Decimal,
+    pub cash: Decimal,
+    pub paid: Decimal,
+    pub invested: Decimal,
+    pub withdrawn: Decimal,
+    pub fees: Decimal,
+    pub taxes: Decimal,
+    /// Realized gain of currency exchanges against reference rates.
+    pub fx_gain: Decimal,
+}
+
+impl Totals {
+    /// Adds the snapshot converted from the asset currency.
+    /// Rates are prices of one unit of the currency in the base currency.
+    pub fn add_snapshot(
+        &mut self,
+        snapshot: &AssetSnapshot,
+        rate: Rate,
+        target_rate: Rate,
+    ) -> Result<(), Error> {
+        check_rate(target_rate)?;
+        let convert = |val: Decimal| val * rate / target_rate;
+        self.value += convert(snapshot.quantity * snapshot.price);
+        self.paid += convert(snapshot.paid);
+        self.invested += convert(snapshot.invested);
+        self.withdrawn += convert(snapshot.withdrawn);
+        self.fees += convert(snapshot.fees);
+        self.taxes += convert(snapshot.taxes);
+        Ok(())
+    }
+
+    /// Adds the cash balance converted from its currency.
+    pub fn add_cash(
+        &mut self,
+        balance: Decimal,
+        rate: Rate,
+        target_rate: Rate,
+    ) -> Result<(), Error> {
+        check_rate(target_rate)?;
+        let balance = balance * rate / target_rate;
+        self.value += balance;
+        self.cash += balance;
+        Ok(())
+    }
+
+    /// Adds the exchange result given in the base currency.
+    pub fn add_fx(&mut self, cost: &FxCost, target_rate: Rate) -> Result<(), Error> {
+        check_rate(target_rate)?;
+        self.fx_gain -= cost.cost / target_rate;
+        self.fees += cost.fee / target_rate;
+        Ok(())
+    }
+
+    /// Converts totals into another currency.
+    pub fn convert(&self, rate: Rate, target_rate: Rate) -> Result<Self, Error> {
+        check_rate(target_rate)?;
+        let convert = |val: Decimal| val * rate / target_rate;
+        Ok(Totals {
+            value: convert(self.value),
+            cash: convert(self.cash),
+            paid: convert(self.paid),
+            invested: convert(self.invested),
+            withdrawn: convert(self.withdrawn),
+            fees: convert(self.fees),
+            taxes: convert(self.taxes),
+            fx_gain: convert(self.fx_gain),
+        })
+    }
+}
+
+/// Nothing can be converted into a currency with a zero rate.
+fn check_rate(target_rate: Rate) -> Result<(), Error> {
+    if target_rate.is_zero() {
+        return Err(Error::msg("Target currency rate is zero"));
+    }
+    Ok(())
+}
+
+impl AddAssign for Totals {
+    fn add_assign(&mut self, rhs: Self) {
+        self.value += rhs.value;
+        self.cash += rhs.cash;
+        self.paid += rhs.paid;
+        self.invested += rhs.invested;
+        self.withdrawn += rhs.withdrawn;
+        self.fees += rhs.fees;
+        self.taxes += rhs.taxes;
+        self.fx_gain += rhs.fx_gain;
+    }
+}
+
+impl Display for Totals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "value: {}", self.value)?;
+        writeln!(f, "cash: {}", self.cash)?;
+        writeln!(f, "paid: {}", self.paid)?;
+        writeln!(f, "invested: {}", self.invested)?;
+        writeln!(f, "withdrawn: {}", self.withdrawn)?;
+        writeln!(f, "fees: {}", self.fees)?;
+        writeln!(f, "taxes: {}", self.taxes)?;
+        writeln!(f, "fx gain: {}", self.fx_gain)?;
+        Ok(())
+    }
+}
+
+pub struct AccountStatistic {
+    pub account: Account,
+    /// Last snapshot of each asset in the asset currency.
+    pub assets: Vec<(Asset, AssetSnapshot)>,
+    /// Cash balances in their currencies.
+    pub cash: Vec<CashBalance>,
+    /// Totals in the account currency.
+    pub totals: Totals,
+}
+
+pub struct AccountsStatistic {
+    pub accounts: Vec<AccountStatistic>,
+    /// Totals of all accounts in the base currency.
+    pub totals: Totals,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::{dec, rate};
+
+    #[test]
+    fn test_add_snapshot() {
+        let snapshot = AssetSnapshot {
+            price: dec("10"),
+            quantity: dec("3"),
+            paid: dec("1"),
+            invested: dec("20"),
+            ..Default::default()
+        };
+        let mut totals = Totals::default();
+        // EUR asset in a RUB account, base is USD.
+        totals
+            .add_snapshot(&snapshot, rate("1.1"), rate("0.01"))
+            .unwrap();
+        assert_eq!(totals.value, dec("3300"));
+        assert_eq!(totals.paid, dec("110"));
+        assert_eq!(totals.invested, dec("2200"));
+
+        totals
+            .add_cash(dec("-1.1"), rate("1"), rate("0.01"))
+            .unwrap();
+        assert_eq!(totals.value, dec("3190"));
+        assert_eq!(totals.cash, dec("-110"));
+
+        totals += totals.clone();
+        assert_eq!(totals.value, dec("6380"));
+        assert_eq!(
+            totals.convert(rate("0.01"), rate("1")).unwrap().value,
+            dec("63.8")
+        );
+        assert!(totals.convert(rate("1"), rate("0")).is_err());
+        assert!(totals.add_cash(dec("1"), rate("1"), rate("0")).is_err());
+
+        // JPY cash in a USD account.
+        let mut jpy = Totals::default();
+        jpy.add_cash(dec("10000"), rate("0.0067"), rate("1"))
+            .unwrap();
+        assert_eq!(jpy.cash, dec("67"));
+
+        totals
+            .add_fx(
+                &FxCost {
+                    implied_rate: dec("1.11"),
+                    reference_rate: dec("1.1"),
+                    cost: dec("10"),
+                    fee: dec("2"),
+                },
+                rate("0.5"),
+            )
+            .unwrap();
+        assert_eq!(totals.fx_gain, dec("-20"));
+        assert_eq!(totals.fees, dec("4"));
+    }
+}
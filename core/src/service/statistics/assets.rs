@@ -1,26 +1,89 @@
 use crate::dao::model::assets::Asset;
 use crate::dao::model::operations::{AssetOperation, OperationType};
-use crate::service::decimal::Decimal;
+use crate::service::decimal::{Decimal, Rate};
+use crate::service::statistics::currency::{CurrencyGraph, CurrencyStatistic, Point};
 use chrono::NaiveDateTime;
+use std::collections::HashMap;
 use std::fmt::Display;
 
 pub struct AssetStatistic {
     pub asset: Asset,
     snapshots: Vec<AssetSnapshot>,
+    /// Currency rate recorded on the operation of each snapshot, `None` for corporate actions.
+    rate_ids: Vec<Option<i64>>,
+    /// Snapshots taken right before each corporate action.
+    before_actions: Vec<(i64, AssetSnapshot)>,
+}
+
+/// Corporate action applied to the asset position.
+#[derive(Debug, Clone)]
+pub struct PositionChange {
+    /// Corporate action id.
+    pub action_id: i64,
+    pub date: NaiveDateTime,
+    pub effect: ActionEffect,
+}
+
+#[derive(Debug, Clone)]
+pub enum ActionEffect {
+    /// Whole position is moved to another asset.
+    Out,
+    /// Share of the cost basis is moved to another asset.
+    CostOut(Decimal),
+    /// Position received from another asset.
+    In(AssetSnapshot),
 }
 
 impl AssetStatistic {
     pub fn new(asset: Asset, operation: Vec<AssetOperation>) -> Self {
+        Self::with_changes(asset, operation, Vec::new())
+    }
+
+    /// Replays operations together with corporate actions.
+    /// Actions are applied after operations with the same date.
+    pub fn with_changes(
+        asset: Asset,
+        mut operation: Vec<AssetOperation>,
+        mut changes: Vec<PositionChange>,
+    ) -> Self {
+        operation.sort_by_key(|op| op.operation_date);
+        changes.sort_by_key(|change| (change.date, change.action_id));
+
         let mut snapshots = Vec::<AssetSnapshot>::new();
-        for op in operation {
-            if let Some(snapshot) = snapshots.last() {
-                snapshots.push(snapshot.make_next(op));
-            } else {
-                snapshots.push(AssetSnapshot::default().make_next(op));
+        let mut rate_ids = Vec::new();
+        let mut before_actions = Vec::new();
+        let mut changes = changes.into_iter().peekable();
+        let mut operation = operation.into_iter().peekable();
+        loop {
+            let last = snapshots.last().cloned().unwrap_or_default();
+            let apply_change = match (operation.peek(), changes.peek()) {
+                (Some(op), Some(change)) => change.date < op.operation_date,
+                (None, Some(_)) => true,
+                (Some(_), None) => false,
+                (None, None) => break,
+            };
+            if apply_change {
+                if let Some(change) = changes.next() {
+                    snapshots.push(last.apply(&change));
+                    rate_ids.push(None);
+                    before_actions.push((change.action_id, last));
+                }
+            } else if let Some(op) = operation.next() {
+                rate_ids.push(Some(op.currency_rate));
+                snapshots.push(last.make_next(op));
             }
         }
 
-        Self { asset, snapshots }
+        Self {
+            asset,
+            snapshots,
+            rate_ids,
+            before_actions,
+        }
+    }
+
+    pub fn snapshots(&self) -> &[AssetSnapshot] {
+        &self.snapshots
     }
 
     pub fn get_last_snapshot(&self) -> Option<&AssetSnapshot> {
@@ -30,9 +93,70 @@ impl AssetStatistic {
     pub fn get_latest_snapshot(&self, date: NaiveDateTime) -> Option<&AssetSnapshot> {
         self.snapshots.iter().rev().find(|s| s.date <= date)
     }
+
+    /// Last known price at every date.
+    pub fn price_graph(&self, dates: &[NaiveDateTime]) -> CurrencyGraph {
+        let points = dates
+            .iter()
+            .filter_map(|&date| {
+                self.get_latest_snapshot(date).map(|snapshot| Point {
+                    date,
+                    value: snapshot.price,
+                })
+            })
+            .collect();
+        CurrencyGraph {
+            ticker: self.asset.ticker.clone(),
+            points,
+        }
+    }
+
+    /// Snapshots in the base currency. Flows are converted with the rate recorded on their
+    /// operation, `rates` maps currency rate ids to rates. Corporate actions and valuation use
+    /// the asset currency rate at the snapshot date, or the last operation rate if it is unknown.
+    pub fn base_snapshots(
+        &self,
+        rates: &HashMap<i64, Rate>,
+        currency: &CurrencyStatistic,
+    ) -> Vec<BaseSnapshot> {
+        let mut base = Vec::<BaseSnapshot>::with_capacity(self.snapshots.len());
+        let mut prev = AssetSnapshot::default();
+        let mut last_rate = None;
+        for (snapshot, rate_id) in self.snapshots.iter().zip(&self.rate_ids) {
+            let flow_rate = rate_id.and_then(|id| rates.get(&id).copied());
+            last_rate = flow_rate.or(last_rate);
+            let rate = currency
+                .get_rate(snapshot.date)
+                .or(last_rate)
+                .unwrap_or_default();
+            let flow_rate = flow_rate.unwrap_or(rate);
+            let last = base.last().cloned().unwrap_or_default();
+            let flow = |cur: Decimal, prev: Decimal| (cur - prev) * flow_rate;
+            base.push(BaseSnapshot {
+                date: snapshot.date,
+                rate,
+                value: snapshot.quantity * snapshot.price * rate,
+                invested: last.invested + flow(snapshot.invested, prev.invested),
+                withdrawn: last.withdrawn + flow(snapshot.withdrawn, prev.withdrawn),
+                paid: last.paid + flow(snapshot.paid, prev.paid),
+                fees: last.fees + flow(snapshot.fees, prev.fees),
+                taxes: last.taxes + flow(snapshot.taxes, prev.taxes),
+            });
+            prev = snapshot.clone();
+        }
+        base
+    }
+
+    /// Position right before the corporate action.
+    pub fn before_action(&self, action_id: i64) -> Option<&AssetSnapshot> {
+        self.before_actions
+            .iter()
+            .find(|(id, _)| *id == action_id)
+            .map(|(_, snapshot)| snapshot)
+    }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct AssetSnapshot {
     /// Snapshot date
     pub date: NaiveDateTime,
@@ -46,44 +170,124 @@ pub struct AssetSnapshot {
     pub invested: Decimal,
     /// Total withdrawn from asset
     pub withdrawn: Decimal,
+    /// Total fees paid
+    pub fees: Decimal,
+    /// Total taxes paid
+    pub taxes: Decimal,
 }
 
 impl AssetSnapshot {
+    /// Value plus income less net investment and costs.
+    pub fn gain(&self) -> Decimal {
+        self.quantity * self.price - self.invested + self.paid - self.fees - self.taxes
+    }
+
     fn make_next(&self, operation: AssetOperation) -> Self {
-        let mut next = AssetSnapshot::default();
-        next.date = operation.operation_date;
+        let mut next = AssetSnapshot {
+            date: operation.operation_date,
+            ..self.clone()
+        };
+        let amount = operation.operation_amount;
         match operation.operation_type {
             OperationType::UpdatePrice => {
-                next.price = operation.operation_amount;
-                next.quantity = self.quantity;
-                next.paid = self.paid;
-                next.invested = self.invested;
-                next.withdrawn = self.withdrawn;
+                next.price = amount;
             }
             OperationType::Buy => {
-                next.price = self.price;
-                next.quantity = self.quantity + operation.operation_amount;
-                next.paid = self.paid;
-                next.invested = self.invested + (operation.operation_amount * self.price);
-                next.withdrawn = self.withdrawn;
+                next.quantity = self.quantity + amount;
+                next.invested = self.invested + (amount * self.price);
             }
             OperationType::Sell => {
-                next.price = self.price;
-                next.quantity = self.quantity - operation.operation_amount;
-                next.paid = self.paid;
-                next.invested = self.invested - (operation.operation_amount * self.price);
-                next.withdrawn = self.withdrawn + (operation.operation_amount * self.price);
+                next.quantity = self.quantity - amount;
+                next.invested = self.invested - (amount * self.price);
+                next.withdrawn = self.withdrawn + (amount * self.price);
+            }
+            OperationType::Dividend | OperationType::Interest => {
+                next.paid = self.paid + amount;
+            }
+            OperationType::Split => {
+                if amount > Decimal::default() {
+                    next.quantity = self.quantity * amount;
+                    next.price = self.price / amount;
+                }
             }
-            OperationType::Dividend => {
-                next.price = self.price;
-                next.quantity = self.quantity;
-                next.paid = self.paid + operation.operation_amount;
-                next.invested = self.invested;
-                next.withdrawn = self.withdrawn;
+            OperationType::ReverseSplit => {
+                if amount > Decimal::default() {
+                    next.quantity = self.quantity / amount;
+                    next.price = self.price * amount;
+                }
+            }
+            OperationType::Fee => {
+                next.fees = self.fees + amount;
+            }
+            OperationType::Tax => {
+                next.taxes = self.taxes + amount;
+            }
+            OperationType::CashDeposit => {
+                next.price = Decimal::int(1);
+                next.quantity = self.quantity + amount;
+                next.invested = self.invested + amount;
+            }
+            OperationType::CashWithdrawal => {
+                next.price = Decimal::int(1);
+                next.quantity = self.quantity - amount;
+                next.invested = self.invested - amount;
+                next.withdrawn = self.withdrawn + amount;
+            }
+            OperationType::DividendReinvestment => {
+                next.quantity = self.quantity + amount;
+                next.paid = self.paid + (amount * self.price);
+                next.invested = self.invested + (amount * self.price);
+            }
+        }
+        next
+    }
+
+    fn apply(&self, change: &PositionChange) -> Self {
+        let mut next = AssetSnapshot {
+            date: change.date,
+            ..self.clone()
+        };
+        match &change.effect {
+            ActionEffect::Out => {
+                next = AssetSnapshot {
+                    date: change.date,
+                    price: self.price,
+                    ..Default::default()
+                };
+            }
+            ActionEffect::CostOut(share) => {
+                next.invested = self.invested - self.invested * *share;
+            }
+            ActionEffect::In(received) => {
+                next.quantity = self.quantity + received.quantity;
+                next.paid = self.paid + received.paid;
+                next.invested = self.invested + received.invested;
+                next.withdrawn = self.withdrawn + received.withdrawn;
+                next.fees = self.fees + received.fees;
+                next.taxes = self.taxes + received.taxes;
             }
         }
         next
     }
+
+    /// Position received by the target of a merger. Totals are carried over.
+    pub fn merged(&self, ratio: Decimal) -> Self {
+        AssetSnapshot {
+            quantity: self.quantity * ratio,
+            price: Decimal::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Position received by the target of a spin-off.
+    pub fn spun_off(&self, ratio: Decimal, cost_basis: Decimal) -> Self {
+        AssetSnapshot {
+            date: self.date,
+            quantity: self.quantity * ratio,
+            invested: self.invested * cost_basis,
+            ..Default::default()
+        }
+    }
 }
 
 impl Display for AssetSnapshot {
@@ -94,6 +298,233 @@ impl Display for AssetSnapshot {
         writeln!(f, "paid: {}", self.paid)?;
         writeln!(f, "invested: {}", self.invested)?;
         writeln!(f, "withdrawn: {}", self.withdrawn)?;
+        writeln!(f, "fees: {}", self.fees)?;
+        writeln!(f, "taxes: {}", self.taxes)?;
+        Ok(())
+    }
+}
+
+/// Asset snapshot in the base currency.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BaseSnapshot {
+    pub date: NaiveDateTime,
+    /// Asset currency rate used for valuation.
+    pub rate: Rate,
+    pub value: Decimal,
+    pub invested: Decimal,
+    pub withdrawn: Decimal,
+    pub paid: Decimal,
+    pub fees: Decimal,
+    pub taxes: Decimal,
+}
+
+impl BaseSnapshot {
+    pub fn gain(&self) -> Decimal {
+        self.value - self.invested + self.paid - self.fees - self.taxes
+    }
+
+    /// Part of the gain coming from the currency rate change.
+    pub fn fx_gain(&self, snapshot: &AssetSnapshot) -> Decimal {
+        self.gain() - snapshot.gain() * self.rate
+    }
+}
+
+impl Display for BaseSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "date: {}", self.date)?;
+        writeln!(f, "rate: {}", self.rate)?;
+        writeln!(f, "value: {}", self.value)?;
+        writeln!(f, "invested: {}", self.invested)?;
+        writeln!(f, "withdrawn: {}", self.withdrawn)?;
+        writeln!(f, "paid: {}", self.paid)?;
+        writeln!(f, "fees: {}", self.fees)?;
+        writeln!(f, "taxes: {}", self.taxes)?;
+        writeln!(f, "gain: {}", self.gain())?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::model::currency::Currency;
+    use crate::service::decimal::{self, dec};
+    use crate::service::test_utils::{self, rate, time};
+
+    fn op(tp: OperationType, amount: &str) -> AssetOperation {
+        test_utils::op(tp, amount, "2020-01-01 00:00:00", 0)
+    }
+
+    fn asset() -> Asset {
+        Asset {
+            id: 0,
+            name: "Test".to_string(),
+            ticker: "TST".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn replay(ops: Vec<AssetOperation>) -> AssetSnapshot {
+        let stat = AssetStatistic::new(asset(), ops);
+        stat.get_last_snapshot().unwrap().clone()
+    }
+
+    #[test]
+    fn test_split() {
+        let snapshot = replay(vec![
+            op(OperationType::UpdatePrice, "100"),
+            op(OperationType::Buy, "10"),
+            op(OperationType::Split, "4"),
+        ]);
+        assert_eq!(snapshot.quantity, dec("40"));
+        assert_eq!(snapshot.price, dec("25"));
+        assert_eq!(snapshot.invested, dec("1000"));
+
+        let snapshot = replay(vec![
+            op(OperationType::UpdatePrice, "1"),
+            op(OperationType::Buy, "100"),
+            op(OperationType::ReverseSplit, "10"),
+        ]);
+        assert_eq!(snapshot.quantity, dec("10"));
+        assert_eq!(snapshot.price, dec("10"));
+    }
+
+    #[test]
+    fn test_income_and_costs() {
+        let snapshot = replay(vec![
+            op(OperationType::UpdatePrice, "10"),
+            op(OperationType::Buy, "10"),
+            op(OperationType::Dividend, "5"),
+            op(OperationType::Interest, "1"),
+            op(OperationType::Fee, "2"),
+            op(OperationType::Tax, "0.65"),
+            op(OperationType::DividendReinvestment, "1"),
+        ]);
+        assert_eq!(snapshot.quantity, dec("11"));
+        assert_eq!(snapshot.paid, dec("16"));
+        assert_eq!(snapshot.invested, dec("110"));
+        assert_eq!(snapshot.fees, dec("2"));
+        assert_eq!(snapshot.taxes, dec("0.65"));
+    }
+
+    #[test]
+    fn test_cash() {
+        let snapshot = replay(vec![
+            op(OperationType::CashDeposit, "1000"),
+            op(OperationType::CashWithdrawal, "300"),
+        ]);
+        assert_eq!(snapshot.quantity, dec("700"));
+        assert_eq!(snapshot.price, dec("1"));
+        assert_eq!(snapshot.invested, dec("700"));
+        assert_eq!(snapshot.withdrawn, dec("300"));
+    }
+
+    fn change(action_id: i64, date: &str, effect: ActionEffect) -> PositionChange {
+        PositionChange {
+            action_id,
+            date: time(date),
+            effect,
+        }
+    }
+
+    #[test]
+    fn test_merger() {
+        let source = AssetStatistic::with_changes(
+            asset(),
+            vec![
+                op(OperationType::UpdatePrice, "10"),
+                op(OperationType::Buy, "10"),
+                op(OperationType::Dividend, "3"),
+            ],
+            vec![change(1, "2020-02-01 00:00:00", ActionEffect::Out)],
+        );
+        let last = source.get_last_snapshot().unwrap();
+        assert_eq!(last.quantity, dec("0"));
+        assert_eq!(last.invested, dec("0"));
+
+        let received = source.before_action(1).unwrap().merged(dec("0.5"));
+        let target = AssetStatistic::with_changes(
+            asset(),
+            vec![op(OperationType::UpdatePrice, "25")],
+            vec![change(1, "2020-02-01 00:00:00", ActionEffect::In(received))],
+        );
+        let last = target.get_last_snapshot().unwrap();
+        assert_eq!(last.quantity, dec("5"));
+        assert_eq!(last.price, dec("25"));
+        assert_eq!(last.invested, dec("100"));
+        assert_eq!(last.paid, dec("3"));
+    }
+
+    #[test]
+    fn test_spin_off() {
+        let source = AssetStatistic::with_changes(
+            asset(),
+            vec![
+                op(OperationType::UpdatePrice, "10"),
+                op(OperationType::Buy, "10"),
+            ],
+            vec![change(
+                1,
+                "2020-02-01 00:00:00",
+                ActionEffect::CostOut(dec("0.2")),
+            )],
+        );
+        let last = source.get_last_snapshot().unwrap();
+        assert_eq!(last.quantity, dec("10"));
+        assert_eq!(last.invested, dec("80"));
+
+        let received = source
+            .before_action(1)
+            .unwrap()
+            .spun_off(dec("0.5"), dec("0.2"));
+        assert_eq!(received.quantity, dec("5"));
+        assert_eq!(received.invested, dec("20"));
+        assert_eq!(received.paid, dec("0"));
+    }
+
+    #[test]
+    fn test_base_snapshots() {
+        // USD stock in a RUB portfolio bought at 60 RUB/USD and valued at 90.
+        let rates = vec![
+            rate(1, "2020-01-01 00:00:00", "60"),
+            rate(2, "2020-06-01 00:00:00", "90"),
+        ];
+        let rate_map = rates.iter().map(|r| (r.id, r.rate)).collect();
+        let currency = CurrencyStatistic::new(
+            Currency {
+                id: 0,
+                name: "USD".to_string(),
+                ticker: "USD".to_string(),
+            },
+            rates,
+        );
+        let stat = AssetStatistic::new(
+            asset(),
+            vec![
+                test_utils::op(OperationType::UpdatePrice, "100", "2020-01-01 00:00:00", 1),
+                test_utils::op(OperationType::Buy, "2", "2020-01-01 00:00:00", 1),
+                test_utils::op(OperationType::Dividend, "5", "2020-06-01 00:00:00", 2),
+                test_utils::op(OperationType::Sell, "1", "2020-06-01 00:00:00", 2),
+            ],
+        );
+        let base = stat.base_snapshots(&rate_map, &currency);
+        let last = base.last().unwrap();
+        let snapshot = stat.get_last_snapshot().unwrap();
+        assert_eq!(last.rate, decimal::rate("90"));
+        assert_eq!(last.value, dec("9000"));
+        assert_eq!(last.invested, dec("3000"));
+        assert_eq!(last.paid, dec("450"));
+        assert_eq!(last.gain(), dec("6450"));
+        assert_eq!(snapshot.gain(), dec("5"));
+        assert_eq!(last.fx_gain(snapshot), dec("6000"));
+    }
+
+    #[test]
+    fn test_parse_operation_type() {
+        assert_eq!(
+            OperationType::try_from("Split").unwrap(),
+            OperationType::Split
+        );
+        assert!(OperationType::try_from("Unknown").is_err());
+    }
+}
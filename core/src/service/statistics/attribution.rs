@@ -0,0 +1,248 @@
+use crate::dao::model::assets::{Asset, AssetToType, AssetType};
+use crate::service::category::subtree;
+use crate::service::decimal::{Decimal, Rate};
+use crate::service::statistics::assets::{AssetSnapshot, AssetStatistic};
+use crate::service::statistics::currency::CurrencyStatistic;
+use crate::service::statistics::types::type_weights;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::ops::AddAssign;
+
+/// Change of the value between two dates in the base currency.
+///
+/// `end_value - start_value = contributions - withdrawals + price + fx`.
+/// Income, fees and taxes are settled in cash and reported separately.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Attribution {
+    pub start_value: Decimal,
+    pub end_value: Decimal,
+    pub contributions: Decimal,
+    pub withdrawals: Decimal,
+    /// Price moves in the local currency valued at the end rate.
+    pub price: Decimal,
+    /// The rest of the market change, caused by currency moves.
+    pub fx: Decimal,
+    /// Dividends and interest.
+    pub income: Decimal,
+    pub fees: Decimal,
+    pub taxes: Decimal,
+}
+
+impl Attribution {
+    /// Return excluding flows.
+    pub fn total_return(&self) -> Decimal {
+        self.price + self.fx + self.income - self.fees - self.taxes
+    }
+
+    /// Share of the attribution, `weight` is in percent.
+    pub fn scaled(&self, weight: Decimal) -> Self {
+        let scale = |val: Decimal| val * weight / Decimal::int(100);
+        Attribution {
+            start_value: scale(self.start_value),
+            end_value: scale(self.end_value),
+            contributions: scale(self.contributions),
+            withdrawals: scale(self.withdrawals),
+            price: scale(self.price),
+            fx: scale(self.fx),
+            income: scale(self.income),
+            fees: scale(self.fees),
+            taxes: scale(self.taxes),
+        }
+    }
+}
+
+/// Attribution of every asset, of the types of one classification and of the whole portfolio.
+#[derive(Clone)]
+pub struct AttributionReport {
+    pub assets: Vec<(Asset, Attribution)>,
+    pub types: Vec<(AssetType, Attribution)>,
+    pub total: Attribution,
+}
+
+/// Attribution of the asset value change between `from` and `to`.
+/// `rates` maps currency rate ids recorded on operations to rates.
+pub fn attribution(
+    stat: &AssetStatistic,
+    rates: &HashMap<i64, Rate>,
+    currency: &CurrencyStatistic,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Attribution {
+    let base = stat.base_snapshots(rates, currency);
+    let at = |date: NaiveDateTime| {
+        let local = stat.get_latest_snapshot(date).cloned().unwrap_or_default();
+        let base = base
+            .iter()
+            .rev()
+            .find(|s| s.date <= date)
+            .cloned()
+            .unwrap_or_default();
+        let rate = currency.get_rate(date).unwrap_or(base.rate);
+        (local, base, rate)
+    };
+    let (local_start, base_start, rate_start) = at(from);
+    let (local_end, base_end, rate_end) = at(to);
+
+    let local_value = |s: &AssetSnapshot| s.quantity * s.price;
+    let start_value = local_value(&local_start) * rate_start;
+    let end_value = local_value(&local_end) * rate_end;
+    let withdrawals = base_end.withdrawn - base_start.withdrawn;
+    let contributions = base_end.invested - base_start.invested + withdrawals;
+    let price = (local_value(&local_end)
+        - local_value(&local_start)
+        - (local_end.invested - local_start.invested))
+        * rate_end;
+    Attribution {
+        start_value,
+        end_value,
+        contributions,
+        withdrawals,
+        price,
+        fx: end_value - start_value - contributions + withdrawals - price,
+        income: base_end.paid - base_start.paid,
+        fees: base_end.fees - base_start.fees,
+        taxes: base_end.taxes - base_start.taxes,
+    }
+}
+
+/// Aggregates asset attributions by type subtrees, split by the link weights.
+pub fn by_type(
+    types: &[AssetType],
+    links: &[AssetToType],
+    assets: &HashMap<i64, Attribution>,
+) -> Vec<(AssetType, Attribution)> {
+    types
+        .iter()
+        .map(|tp| {
+            let mut total = Attribution::default();
+            for (asset, weight) in type_weights(&subtree(types, tp.id), links) {
+                if let Some(attribution) = assets.get(&asset) {
+                    total += attribution.scaled(weight);
+                }
+            }
+            (tp.clone(), total)
+        })
+        .collect()
+}
+
+impl AddAssign for Attribution {
+    fn add_assign(&mut self, rhs: Self) {
+        self.start_value += rhs.start_value;
+        self.end_value += rhs.end_value;
+        self.contributions += rhs.contributions;
+        self.withdrawals += rhs.withdrawals;
+        self.price += rhs.price;
+        self.fx += rhs.fx;
+        self.income += rhs.income;
+        self.fees += rhs.fees;
+        self.taxes += rhs.taxes;
+    }
+}
+
+impl Display for Attribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "start value: {}", self.start_value)?;
+        writeln!(f, "contributions: {}", self.contributions)?;
+        writeln!(f, "withdrawals: {}", self.withdrawals)?;
+        writeln!(f, "price: {}", self.price)?;
+        writeln!(f, "fx: {}", self.fx)?;
+        writeln!(f, "end value: {}", self.end_value)?;
+        writeln!(f, "income: {}", self.income)?;
+        writeln!(f, "fees: {}", self.fees)?;
+        writeln!(f, "taxes: {}", self.taxes)?;
+        writeln!(f, "total return: {}", self.total_return())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::model::currency::Currency;
+    use crate::dao::model::operations::OperationType;
+    use crate::service::decimal::dec;
+    use crate::service::test_utils::{op, rate, time};
+
+    #[test]
+    fn test_attribution() {
+        let rates = vec![
+            rate(1, "2020-01-01 00:00:00", "1"),
+            rate(2, "2020-06-01 00:00:00", "1.2"),
+            rate(3, "2021-01-01 00:00:00", "1.5"),
+        ];
+        let rate_map = rates.iter().map(|r| (r.id, r.rate)).collect();
+        let currency = CurrencyStatistic::new(
+            Currency {
+                id: 0,
+                name: "EUR".to_string(),
+                ticker: "EUR".to_string(),
+            },
+            rates,
+        );
+        let stat = AssetStatistic::new(
+            Asset::default(),
+            vec![
+                op(OperationType::UpdatePrice, "100", "2020-01-01 00:00:00", 1),
+                op(OperationType::Buy, "10", "2020-01-01 00:00:00", 1),
+                op(OperationType::UpdatePrice, "105", "2020-06-01 00:00:00", 2),
+                op(OperationType::Buy, "10", "2020-06-01 00:00:00", 2),
+                op(OperationType::Dividend, "20", "2020-06-01 00:00:00", 2),
+                op(OperationType::Fee, "3", "2020-06-01 00:00:00", 2),
+                op(OperationType::UpdatePrice, "110", "2021-01-01 00:00:00", 3),
+            ],
+        );
+
+        let attribution = attribution(
+            &stat,
+            &rate_map,
+            &currency,
+            time("2020-03-01 00:00:00"),
+            time("2021-02-01 00:00:00"),
+        );
+        assert_eq!(attribution.start_value, dec("1000"));
+        assert_eq!(attribution.end_value, dec("3300"));
+        assert_eq!(attribution.contributions, dec("1260"));
+        assert_eq!(attribution.withdrawals, dec("0"));
+        assert_eq!(attribution.price, dec("225"));
+        assert_eq!(attribution.fx, dec("815"));
+        assert_eq!(attribution.income, dec("24"));
+        assert_eq!(attribution.fees, dec("3.6"));
+        assert_eq!(attribution.total_return(), dec("1060.4"));
+    }
+
+    #[test]
+    fn test_by_type() {
+        let tp = |id: i64, parent: Option<i64>| AssetType {
+            id,
+            name: id.to_string(),
+            description: "".to_string(),
+            parent,
+            classification: 1,
+        };
+        let types = vec![tp(1, None), tp(2, Some(1))];
+        let links = vec![
+            AssetToType {
+                asset_id: 10,
+                type_id: 2,
+                weight: dec("60"),
+            },
+            AssetToType {
+                asset_id: 11,
+                type_id: 1,
+                weight: dec("100"),
+            },
+        ];
+        let one = Attribution {
+            price: dec("100"),
+            income: dec("10"),
+            ..Default::default()
+        };
+        let assets = HashMap::from([(10, one.clone()), (11, one)]);
+
+        let totals = by_type(&types, &links, &assets);
+        assert_eq!(totals[0].1.price, dec("160"));
+        assert_eq!(totals[1].1.price, dec("60"));
+        assert_eq!(totals[1].1.total_return(), dec("66"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::dao::model::assets::Asset;
+use crate::service::decimal::Decimal;
+use crate::service::statistics::performance::{
+    performance, sample_dates, valuation, AssetHistory, Period,
+};
+use crate::service::statistics::risk::{periods_per_year, returns, std_dev};
+use chrono::{Duration, NaiveDateTime};
+use color_eyre::eyre::Error;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparisonPoint {
+    pub date: NaiveDateTime,
+    pub value: Decimal,
+    /// Value of the benchmark bought with the same cash flows.
+    pub benchmark: Decimal,
+    /// Net money put in since the start, including the start value.
+    pub invested: Decimal,
+}
+
+#[derive(Clone)]
+pub struct Comparison {
+    pub benchmark: Asset,
+    /// Value curves at every step of the range.
+    pub points: Vec<ComparisonPoint>,
+    /// Time-weighted return, in percent.
+    pub twr: Option<Decimal>,
+    /// Benchmark price return, in percent.
+    pub benchmark_return: Option<Decimal>,
+    /// Return minus benchmark return, in percentage points.
+    pub excess: Option<Decimal>,
+    /// Annualized standard deviation of the step return differences, in percent.
+    pub tracking_error: Option<Decimal>,
+}
+
+/// Compares the assets taken with the weights in percent with the benchmark.
+///
+/// The start value is put into the benchmark at `from`, then every change of the invested
+/// amount is replayed at the benchmark price of its date.
+pub fn compare(
+    assets: &[(&AssetHistory, Decimal)],
+    benchmark: &AssetHistory,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    step: Duration,
+) -> Result<Comparison, Error> {
+    let steps = sample_dates(from, to, step)?;
+    let mut dates = assets
+        .iter()
+        .flat_map(|(asset, _)| asset.stat.snapshots().iter().map(|s| s.date))
+        .filter(|date| *date > from && *date <= to)
+        .chain(steps.iter().copied())
+        .collect::<Vec<_>>();
+    dates.sort();
+    dates.dedup();
+
+    let mut units = 0f64;
+    let mut invested = Decimal::default();
+    let mut prev = valuation(assets, from);
+    let mut points = Vec::new();
+    let mut series = Vec::new();
+    for date in dates {
+        let current = valuation(assets, date);
+        let price = benchmark.price(date).filter(|p| *p > Decimal::default());
+        let flow = if date == from {
+            current.value
+        } else {
+            current.invested - prev.invested
+        };
+        if let Some(price) = price {
+            units += f64::from(flow) / f64::from(price);
+        }
+        invested += flow;
+        prev = current;
+        if steps.contains(&date) {
+            let benchmark = price.map_or(0.0, |price| units * f64::from(price));
+            points.push(ComparisonPoint {
+                date,
+                value: current.value,
+                benchmark: Decimal::from_f64(benchmark),
+                invested,
+            });
+            series.push((date, current));
+        }
+    }
+
+    let twr = performance(assets, Period::Custom(from, to), to).twr;
+    let benchmark_return = match (benchmark.price(from), benchmark.price(to)) {
+        (Some(start), Some(end)) if start > Decimal::default() => Some(Decimal::from_f64(
+            (f64::from(end) / f64::from(start) - 1.0) * 100.0,
+        )),
+        _ => None,
+    };
+    let prices = steps
+        .windows(2)
+        .filter_map(|step| {
+            let start = benchmark
+                .price(step[0])
+                .filter(|p| *p > Decimal::default())?;
+            let end = benchmark.price(step[1])?;
+            Some((step[1], f64::from(end) / f64::from(start) - 1.0))
+        })
+        .collect::<HashMap<_, _>>();
+    let differences = returns(&series)
+        .into_iter()
+        .filter_map(|(date, r)| prices.get(&date).map(|b| r - b))
+        .collect::<Vec<_>>();
+    Ok(Comparison {
+        benchmark: benchmark.stat.asset.clone(),
+        points,
+        twr,
+        benchmark_return,
+        excess: twr.zip(benchmark_return).map(|(own, other)| own - other),
+        tracking_error: std_dev(&differences)
+            .map(|d| Decimal::from_f64(d * periods_per_year(step).sqrt() * 100.0)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::model::operations::OperationType;
+    use crate::service::decimal::dec;
+    use crate::service::test_utils::{history, op, time};
+
+    #[test]
+    fn test_compare() {
+        let asset = history(vec![
+            op(OperationType::UpdatePrice, "100", "2023-01-01 00:00:00", 1),
+            op(OperationType::Buy, "10", "2023-01-01 00:00:00", 1),
+            op(OperationType::UpdatePrice, "110", "2023-01-02 00:00:00", 1),
+            op(OperationType::Buy, "10", "2023-01-02 00:00:00", 1),
+            op(OperationType::UpdatePrice, "121", "2023-01-03 00:00:00", 1),
+        ]);
+        let index = history(vec![
+            op(OperationType::UpdatePrice, "50", "2023-01-01 00:00:00", 1),
+            op(OperationType::UpdatePrice, "50", "2023-01-02 00:00:00", 1),
+            op(OperationType::UpdatePrice, "60", "2023-01-03 00:00:00", 1),
+        ]);
+
+        let comparison = compare(
+            &[(&asset, dec("100"))],
+            &index,
+            time("2023-01-01 00:00:00"),
+            time("2023-01-03 00:00:00"),
+            Duration::days(1),
+        )
+        .unwrap();
+        let curve = comparison
+            .points
+            .iter()
+            .map(|p| (p.value, p.benchmark, p.invested))
+            .collect::<Vec<_>>();
+        // 20 units bought for 1000, 22 more for 1100 at the flat price.
+        assert_eq!(
+            curve,
+            vec![
+                (dec("1000"), dec("1000"), dec("1000")),
+                (dec("2200"), dec("2100"), dec("2100")),
+                (dec("2420"), dec("2520"), dec("2100")),
+            ]
+        );
+        assert_eq!(comparison.twr, Some(dec("21")));
+        assert_eq!(comparison.benchmark_return, Some(dec("20")));
+        assert_eq!(comparison.excess, Some(dec("1")));
+        // Step differences are 10% and -10%.
+        assert_eq!(comparison.tracking_error, Some(dec("270.19")));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::dao::model::cash::CashExchange;
+use crate::dao::model::operations::OperationType;
+use crate::service::decimal::{Decimal, Rate};
+use color_eyre::eyre::Error;
+
+/// Currency exchange measured against reference rates at its date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FxCost {
+    /// Units sold per unit bought.
+    pub implied_rate: Decimal,
+    /// Units sold per unit bought by reference rates.
+    pub reference_rate: Decimal,
+    /// Loss against reference rates in the base currency, negative for gain.
+    pub cost: Decimal,
+    /// Fee in the base currency.
+    pub fee: Decimal,
+}
+
+/// Rates are prices of one unit of the currency in the base currency.
+pub fn fx_cost(
+    exchange: &CashExchange,
+    from_rate: Rate,
+    to_rate: Rate,
+    fee_rate: Rate,
+) -> Result<FxCost, Error> {
+    let from_amount: i64 = exchange.from_amount.into();
+    let to_amount: i64 = exchange.to_amount.into();
+    let from_raw: i64 = from_rate.into();
+    let to_raw: i64 = to_rate.into();
+    if from_raw == 0 {
+        return Err(Error::msg("Sold currency rate is zero"));
+    }
+    if to_amount == 0 {
+        return Err(Error::msg("Exchange bought nothing"));
+    }
+    Ok(FxCost {
+        implied_rate: Decimal::from_ratio(from_amount as i128, to_amount as i128),
+        reference_rate: Decimal::from_ratio(to_raw as i128, from_raw as i128),
+        cost: exchange.from_amount * from_rate - exchange.to_amount * to_rate,
+        fee: exchange.fee * fee_rate,
+    })
+}
+
+/// Cash moved by an asset operation at the given price.
+/// Positive for credit and negative for debit.
+pub fn cash_flow(tp: OperationType, amount: Decimal, price: Decimal) -> Decimal {
+    let zero = Decimal::default();
+    match tp {
+        OperationType::Buy => zero - amount * price,
+        OperationType::Sell => amount * price,
+        OperationType::Dividend | OperationType::Interest => amount,
+        OperationType::Fee | OperationType::Tax => zero - amount,
+        OperationType::CashDeposit => zero - amount,
+        OperationType::CashWithdrawal => amount,
+        OperationType::UpdatePrice
+        | OperationType::Split
+        | OperationType::ReverseSplit
+        | OperationType::DividendReinvestment => zero,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::{dec, rate};
+    use chrono::NaiveDateTime;
+
+    #[test]
+    fn test_fx_cost() {
+        // 1000 USD sold for 900 EUR with 2 USD fee, reference is 1.1 USD per EUR.
+        let exchange = CashExchange {
+            id: 1,
+            account: 1,
+            exchange_date: NaiveDateTime::default(),
+            from_currency: 1,
+            from_amount: dec("1000"),
+            to_currency: 2,
+            to_amount: dec("900"),
+            fee: dec("2"),
+            fee_currency: Some(1),
+        };
+        let cost = fx_cost(&exchange, rate("1"), rate("1.1"), rate("1")).unwrap();
+        assert_eq!(cost.implied_rate, dec("1.11"));
+        assert_eq!(cost.reference_rate, dec("1.1"));
+        assert_eq!(cost.cost, dec("10"));
+        assert_eq!(cost.fee, dec("2"));
+
+        // Better than reference.
+        let cost = fx_cost(&exchange, rate("1"), rate("1.12"), rate("1")).unwrap();
+        assert_eq!(cost.cost, dec("-8"));
+
+        assert!(fx_cost(&exchange, rate("0"), rate("1.1"), rate("1")).is_err());
+    }
+
+    #[test]
+    fn test_cash_flow() {
+        let price = dec("12.5");
+        assert_eq!(cash_flow(OperationType::Buy, dec("4"), price), dec("-50"));
+        assert_eq!(cash_flow(OperationType::Sell, dec("2"), price), dec("25"));
+        assert_eq!(
+            cash_flow(OperationType::Dividend, dec("3"), price),
+            dec("3")
+        );
+        assert_eq!(
+            cash_flow(OperationType::Fee, dec("1.5"), price),
+            dec("-1.5")
+        );
+        assert_eq!(
+            cash_flow(OperationType::CashDeposit, dec("100"), price),
+            dec("-100")
+        );
+        assert_eq!(
+            cash_flow(OperationType::DividendReinvestment, dec("1"), price),
+            dec("0")
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use crate::service::decimal::Decimal;
+use crate::service::statistics::risk::{covariance, std_dev};
+
+/// Prices or rates sampled at the same dates, `None` before the first known value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignedSeries {
+    pub name: String,
+    pub values: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationMatrix {
+    pub names: Vec<String>,
+    /// `values[i][j]` is the correlation of the returns of `names[i]` and `names[j]`,
+    /// `None` if they overlap less than the minimum or one of them is flat.
+    pub values: Vec<Vec<Option<Decimal>>>,
+    /// Series with fewer returns than the minimum overlap.
+    pub excluded: Vec<String>,
+}
+
+/// Correlation of the periodic returns of the series.
+/// A pair is compared over the periods where both have a return.
+pub fn correlation(series: &[AlignedSeries], min_overlap: usize) -> CorrelationMatrix {
+    let (included, excluded): (Vec<_>, Vec<_>) = series
+        .iter()
+        .map(|s| (s.name.clone(), returns(&s.values)))
+        .partition(|(_, returns)| returns.iter().flatten().count() >= min_overlap.max(2));
+    let values = included
+        .iter()
+        .map(|(_, a)| {
+            included
+                .iter()
+                .map(|(_, b)| {
+                    let (a, b): (Vec<f64>, Vec<f64>) =
+                        a.iter().zip(b).filter_map(|(a, b)| a.zip(*b)).unzip();
+                    if a.len() < min_overlap.max(2) {
+                        return None;
+                    }
+                    let deviation = std_dev(&a)? * std_dev(&b)?;
+                    if deviation <= 0.0 {
+                        return None;
+                    }
+                    covariance(&a, &b).map(|c| Decimal::from_f64(c / deviation))
+                })
+                .collect()
+        })
+        .collect();
+    CorrelationMatrix {
+        names: included.into_iter().map(|(name, _)| name).collect(),
+        values,
+        excluded: excluded.into_iter().map(|(name, _)| name).collect(),
+    }
+}
+
+/// Return of every period ending at the sample, `None` if either end is missing.
+fn returns(values: &[Option<f64>]) -> Vec<Option<f64>> {
+    values
+        .windows(2)
+        .map(|step| match (step[0], step[1]) {
+            (Some(start), Some(end)) if start > 0.0 => Some(end / start - 1.0),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+
+    fn series(name: &str, values: &[Option<&str>]) -> AlignedSeries {
+        AlignedSeries {
+            name: name.to_string(),
+            values: values
+                .iter()
+                .map(|v| v.map(|v| f64::from(dec(v))))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_correlation() {
+        let series = vec![
+            series("A", &[Some("100"), Some("110"), Some("99"), Some("108.9")]),
+            series("B", &[Some("50"), Some("60"), Some("40"), Some("55")]),
+            series("C", &[Some("10"), Some("9"), Some("9.9"), Some("8.91")]),
+            series("D", &[None, None, Some("1"), Some("2")]),
+            series("E", &[Some("1"), Some("1"), Some("1"), Some("1")]),
+        ];
+
+        let matrix = correlation(&series, 3);
+        assert_eq!(matrix.names, vec!["A", "B", "C", "E"]);
+        assert_eq!(matrix.excluded, vec!["D"]);
+        assert_eq!(matrix.values[0][0], Some(dec("1")));
+        assert_eq!(matrix.values[0][1], matrix.values[1][0]);
+        assert!(matrix.values[0][1] > Some(dec("0.9")));
+        assert_eq!(matrix.values[0][2], Some(dec("-1")));
+        assert_eq!(matrix.values[0][3], None);
+    }
+}
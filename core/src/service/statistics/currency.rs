@@ -1,5 +1,5 @@
 use crate::dao::model::currency::{Currency, CurrencyRate};
-use crate::service::decimal::Decimal;
+use crate::service::decimal::{Decimal, Rate};
 use chrono::{Duration, NaiveDateTime};
 
 pub struct CurrencyStatistic {
@@ -14,7 +14,7 @@ impl CurrencyStatistic {
             panic!("Invalid currency rates");
         }
 
-        rates.sort_by(|a, b| a.date.cmp(&b.date));
+        rates.sort_by_key(|r| r.date);
         Self {
             id: curr.id,
             ticker: curr.name,
@@ -30,7 +30,7 @@ impl CurrencyStatistic {
         &self.ticker
     }
 
-    pub fn get_rate(&self, date: NaiveDateTime) -> Option<Decimal> {
+    pub fn get_rate(&self, date: NaiveDateTime) -> Option<Rate> {
         let mut rate = None;
         for r in &self.rates {
             if r.date <= date {
@@ -48,30 +48,26 @@ impl CurrencyStatistic {
             .filter(|r| r.date >= start_date && r.date <= end_date)
             .map(|r| Point {
                 date: r.date,
-                value: r.rate,
+                value: r.rate.to_decimal(),
             })
             .collect()
     }
 
-    pub fn build_graph(
-        &self,
-        start_date: NaiveDateTime,
-        end_date: NaiveDateTime,
-        point_size: Duration,
-    ) -> CurrencyGraph {
+    pub fn build_graph(&self, dates: &[NaiveDateTime], point_size: Duration) -> CurrencyGraph {
         let mut points = Vec::new();
-        let mut date = start_date;
-        while date <= end_date {
+        for &date in dates {
             let segment_end = date + point_size;
             let point = average_rate(&self.get_points(date, segment_end));
             if point == Decimal::int(0) {
                 if let Some(r) = self.get_rate(date) {
-                    points.push(Point { date, value: r })
+                    points.push(Point {
+                        date,
+                        value: r.to_decimal(),
+                    })
                 }
             } else {
                 points.push(Point { date, value: point });
             }
-            date = segment_end;
         }
 
         CurrencyGraph {
@@ -109,6 +105,8 @@ pub struct CurrencyGraph {
 mod tests {
     use super::*;
     use crate::service::decimal::dec;
+    use crate::service::statistics::performance::sample_dates;
+    use crate::service::test_utils::{rate, time};
 
     fn make_usd_currency_statistic() -> CurrencyStatistic {
         CurrencyStatistic::new(
@@ -118,66 +116,16 @@ mod tests {
                 ticker: "USD".to_string(),
             },
             vec![
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("1"),
-                    date: time("2020-01-01 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("2"),
-                    date: time("2020-01-02 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("3"),
-                    date: time("2020-01-03 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("4"),
-                    date: time("2020-01-04 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("5"),
-                    date: time("2020-01-05 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("6"),
-                    date: time("2020-01-06 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("7"),
-                    date: time("2020-01-07 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("8"),
-                    date: time("2020-01-08 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("9"),
-                    date: time("2020-01-09 00:00:00"),
-                },
-                CurrencyRate {
-                    id: 0,
-                    currency_id: 0,
-                    rate: dec("10"),
-                    date: time("2020-01-10 00:00:00"),
-                },
+                rate(0, "2020-01-01 00:00:00", "1"),
+                rate(0, "2020-01-02 00:00:00", "2"),
+                rate(0, "2020-01-03 00:00:00", "3"),
+                rate(0, "2020-01-04 00:00:00", "4"),
+                rate(0, "2020-01-05 00:00:00", "5"),
+                rate(0, "2020-01-06 00:00:00", "6"),
+                rate(0, "2020-01-07 00:00:00", "7"),
+                rate(0, "2020-01-08 00:00:00", "8"),
+                rate(0, "2020-01-09 00:00:00", "9"),
+                rate(0, "2020-01-10 00:00:00", "10"),
             ],
         )
     }
@@ -186,11 +134,7 @@ mod tests {
     fn test_build_graph() {
         let stat = make_usd_currency_statistic();
         assert_eq!(
-            stat.build_graph(
-                time("2020-01-01 00:00:00"),
-                time("2020-01-01 00:00:00"),
-                Duration::hours(1)
-            ),
+            stat.build_graph(&[time("2020-01-01 00:00:00")], Duration::hours(1)),
             CurrencyGraph {
                 ticker: "USD".to_string(),
                 points: vec![Point {
@@ -200,12 +144,14 @@ mod tests {
             }
         );
 
+        let dates = sample_dates(
+            time("2020-01-01 00:00:00"),
+            time("2020-01-02 00:00:00"),
+            Duration::hours(1),
+        )
+        .unwrap();
         assert_eq!(
-            stat.build_graph(
-                time("2020-01-01 00:00:00"),
-                time("2020-01-02 00:00:00"),
-                Duration::hours(1)
-            ),
+            stat.build_graph(&dates, Duration::hours(1)),
             CurrencyGraph {
                 ticker: "USD".to_string(),
                 points: vec![
@@ -402,31 +348,31 @@ mod tests {
         assert_eq!(stat.get_rate(time("2019-01-01 00:00:00")), None);
         assert_eq!(
             stat.get_rate(time("2020-01-01 00:00:00")),
-            Some(Decimal::int(1))
+            Some(Rate::int(1))
         );
         assert_eq!(
             stat.get_rate(time("2020-01-02 00:00:00")),
-            Some(Decimal::int(2))
+            Some(Rate::int(2))
         );
         assert_eq!(
             stat.get_rate(time("2020-01-03 00:00:00")),
-            Some(Decimal::int(3))
+            Some(Rate::int(3))
         );
         assert_eq!(
             stat.get_rate(time("2020-01-04 00:00:00")),
-            Some(Decimal::int(4))
+            Some(Rate::int(4))
         );
         assert_eq!(
             stat.get_rate(time("2020-01-04 23:59:59")),
-            Some(Decimal::int(4))
+            Some(Rate::int(4))
         );
         assert_eq!(
             stat.get_rate(time("2020-01-05 00:00:00")),
-            Some(Decimal::int(5))
+            Some(Rate::int(5))
         );
         assert_eq!(
             stat.get_rate(time("2020-01-11 00:00:00")),
-            Some(Decimal::int(10))
+            Some(Rate::int(10))
         );
     }
 
@@ -478,8 +424,4 @@ mod tests {
         ];
         assert_eq!(average_rate(&points), dec("1.50"));
     }
-
-    fn time(val: &str) -> NaiveDateTime {
-        NaiveDateTime::parse_from_str(val, "%Y-%m-%d %H:%M:%S").unwrap()
-    }
 }
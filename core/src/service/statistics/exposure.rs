@@ -0,0 +1,123 @@
+use crate::service::decimal::Decimal;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Part of the portfolio depending on one currency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exposure {
+    pub currency: i64,
+    pub ticker: String,
+    /// Value of the assets and cash in the base currency.
+    pub value: Decimal,
+    /// Share of the total value, in percent.
+    pub share: Decimal,
+    /// Change of the total value if the currency moves by the shock against the base one.
+    pub impact: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExposureReport {
+    pub date: NaiveDateTime,
+    pub total: Decimal,
+    /// Sorted by value, largest first.
+    pub exposures: Vec<Exposure>,
+}
+
+/// Groups position values in the base currency by their exposure currency.
+/// `shock` is the currency move in percent, the base currency has no impact.
+pub fn exposure(
+    date: NaiveDateTime,
+    positions: &[(i64, Decimal)],
+    tickers: &HashMap<i64, String>,
+    base: i64,
+    shock: Decimal,
+) -> ExposureReport {
+    let mut values = HashMap::<i64, Decimal>::new();
+    for (currency, value) in positions {
+        *values.entry(*currency).or_default() += *value;
+    }
+    let total = values
+        .values()
+        .fold(Decimal::default(), |acc, val| acc + *val);
+    let mut exposures = values
+        .into_iter()
+        .map(|(currency, value)| {
+            let share = if total > Decimal::default() {
+                let (value_raw, total_raw): (i64, i64) = (value.into(), total.into());
+                Decimal::from_ratio(value_raw as i128 * 100, total_raw as i128)
+            } else {
+                Decimal::default()
+            };
+            let impact = if currency == base {
+                Decimal::default()
+            } else {
+                value * shock / Decimal::int(100)
+            };
+            Exposure {
+                currency,
+                ticker: tickers.get(&currency).cloned().unwrap_or_default(),
+                value,
+                share,
+                impact,
+            }
+        })
+        .collect::<Vec<_>>();
+    exposures.sort_by(|a, b| b.value.cmp(&a.value).then(a.ticker.cmp(&b.ticker)));
+    ExposureReport {
+        date,
+        total,
+        exposures,
+    }
+}
+
+impl Display for ExposureReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}: total {}", self.date, self.total)?;
+        for exposure in &self.exposures {
+            writeln!(
+                f,
+                "{}: {} ({}%), impact {}",
+                exposure.ticker, exposure.value, exposure.share, exposure.impact
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+
+    #[test]
+    fn test_exposure() {
+        let tickers = HashMap::from([
+            (1, "RUB".to_string()),
+            (2, "USD".to_string()),
+            (3, "EUR".to_string()),
+        ]);
+        let positions = vec![
+            (1, dec("500")),
+            (2, dec("300")),
+            (2, dec("200")),
+            (3, dec("-100")),
+            (1, dec("100")),
+        ];
+        let report = exposure(NaiveDateTime::default(), &positions, &tickers, 1, dec("10"));
+        assert_eq!(report.total, dec("1000"));
+        let rows = report
+            .exposures
+            .iter()
+            .map(|e| (e.ticker.as_str(), e.value, e.share, e.impact))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                ("RUB", dec("600"), dec("60"), dec("0")),
+                ("USD", dec("500"), dec("50"), dec("50")),
+                ("EUR", dec("-100"), dec("-10"), dec("-10")),
+            ]
+        );
+    }
+}
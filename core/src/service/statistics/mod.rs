@@ -1,3 +1,14 @@
+pub mod account;
 pub mod assets;
+pub mod attribution;
+pub mod benchmark;
+pub mod cash;
+pub mod correlation;
 pub mod currency;
+pub mod exposure;
+pub mod performance;
+pub mod rebalance;
+pub mod risk;
+pub mod rolling;
+pub mod target;
 pub mod types;
@@ -0,0 +1,382 @@
+use crate::dao::model::assets::{Asset, AssetType};
+use crate::service::decimal::Decimal;
+use crate::service::statistics::assets::{AssetStatistic, BaseSnapshot};
+use crate::service::statistics::currency::CurrencyStatistic;
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime};
+use color_eyre::eyre::Error;
+use std::fmt::Display;
+use std::ops::AddAssign;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    MonthToDate,
+    QuarterToDate,
+    YearToDate,
+    /// Trailing number of years.
+    Years(u32),
+    SinceInception,
+    Custom(NaiveDateTime, NaiveDateTime),
+}
+
+impl Period {
+    /// MTD, QTD, YTD, 1Y, 3Y and since inception.
+    pub fn standard() -> Vec<Period> {
+        vec![
+            Period::MonthToDate,
+            Period::QuarterToDate,
+            Period::YearToDate,
+            Period::Years(1),
+            Period::Years(3),
+            Period::SinceInception,
+        ]
+    }
+
+    /// Start and end of the period ending now.
+    pub fn range(
+        &self,
+        now: NaiveDateTime,
+        inception: NaiveDateTime,
+    ) -> (NaiveDateTime, NaiveDateTime) {
+        let day = |month: u32| {
+            NaiveDate::from_ymd_opt(now.year(), month, 1)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .unwrap_or(now)
+        };
+        match *self {
+            Period::MonthToDate => (day(now.month()), now),
+            Period::QuarterToDate => (day(now.month0() / 3 * 3 + 1), now),
+            Period::YearToDate => (day(1), now),
+            Period::Years(years) => (
+                now.checked_sub_months(Months::new(years * 12))
+                    .unwrap_or(inception),
+                now,
+            ),
+            Period::SinceInception => (inception, now),
+            Period::Custom(from, to) => (from, to),
+        }
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Period::MonthToDate => write!(f, "MTD"),
+            Period::QuarterToDate => write!(f, "QTD"),
+            Period::YearToDate => write!(f, "YTD"),
+            Period::Years(years) => write!(f, "{}Y", years),
+            Period::SinceInception => write!(f, "ITD"),
+            Period::Custom(from, to) => write!(f, "{} - {}", from.date(), to.date()),
+        }
+    }
+}
+
+/// Cumulative totals of a position at a date in the base currency.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Valuation {
+    pub value: Decimal,
+    pub invested: Decimal,
+    pub paid: Decimal,
+    /// Fees and taxes.
+    pub costs: Decimal,
+}
+
+impl Valuation {
+    pub fn gain(&self) -> Decimal {
+        self.value - self.invested + self.paid - self.costs
+    }
+
+    /// Share of the valuation, `weight` is in percent.
+    pub fn scaled(&self, weight: Decimal) -> Self {
+        let scale = |val: Decimal| val * weight / Decimal::int(100);
+        Valuation {
+            value: scale(self.value),
+            invested: scale(self.invested),
+            paid: scale(self.paid),
+            costs: scale(self.costs),
+        }
+    }
+}
+
+impl AddAssign for Valuation {
+    fn add_assign(&mut self, rhs: Self) {
+        self.value += rhs.value;
+        self.invested += rhs.invested;
+        self.paid += rhs.paid;
+        self.costs += rhs.costs;
+    }
+}
+
+/// Asset statistic with everything needed to value it in the base currency at any date.
+pub struct AssetHistory {
+    pub stat: AssetStatistic,
+    pub base: Vec<BaseSnapshot>,
+    pub currency: CurrencyStatistic,
+}
+
+impl AssetHistory {
+    pub fn new(stat: AssetStatistic, base: Vec<BaseSnapshot>, currency: CurrencyStatistic) -> Self {
+        AssetHistory {
+            stat,
+            base,
+            currency,
+        }
+    }
+
+    /// Value at the currency rate of the date, flows at the rates they were made at.
+    pub fn valuation(&self, date: NaiveDateTime) -> Valuation {
+        let Some(local) = self.stat.get_latest_snapshot(date) else {
+            return Valuation::default();
+        };
+        let base = self
+            .base
+            .iter()
+            .rev()
+            .find(|s| s.date <= date)
+            .cloned()
+            .unwrap_or_default();
+        let rate = self.currency.get_rate(date).unwrap_or(base.rate);
+        Valuation {
+            value: local.quantity * local.price * rate,
+            invested: base.invested,
+            paid: base.paid,
+            costs: base.fees + base.taxes,
+        }
+    }
+
+    /// Unit price in the base currency.
+    pub fn price(&self, date: NaiveDateTime) -> Option<Decimal> {
+        let local = self.stat.get_latest_snapshot(date)?;
+        let rate = self.currency.get_rate(date).or_else(|| {
+            self.base
+                .iter()
+                .rev()
+                .find(|s| s.date <= date)
+                .map(|s| s.rate)
+        })?;
+        Some(local.price * rate)
+    }
+
+    pub fn inception(&self) -> Option<NaiveDateTime> {
+        self.stat.snapshots().first().map(|s| s.date)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Performance {
+    pub period: Period,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub start_value: Decimal,
+    pub end_value: Decimal,
+    /// Change of the value plus income less net investment and costs.
+    pub gain: Decimal,
+    /// Time-weighted return, in percent. `None` if nothing was held in the period.
+    pub twr: Option<Decimal>,
+    /// Dividends and interest.
+    pub income: Decimal,
+}
+
+/// Performance of the assets taken with the weights in percent.
+///
+/// The period is split at every snapshot of the assets, flows are treated as made at the
+/// end of the sub-period. Sub-periods starting without value are skipped.
+pub fn performance(
+    assets: &[(&AssetHistory, Decimal)],
+    period: Period,
+    now: NaiveDateTime,
+) -> Performance {
+    let inception = assets
+        .iter()
+        .filter_map(|(asset, _)| asset.inception())
+        .min()
+        .unwrap_or(now);
+    let (from, to) = period.range(now, inception);
+    let mut dates = assets
+        .iter()
+        .flat_map(|(asset, _)| asset.stat.snapshots().iter().map(|s| s.date))
+        .filter(|date| *date > from && *date < to)
+        .collect::<Vec<_>>();
+    dates.push(from);
+    dates.push(to);
+    dates.sort();
+    dates.dedup();
+
+    let series = dates
+        .into_iter()
+        .map(|date| valuation(assets, date))
+        .collect::<Vec<_>>();
+    let start = series.first().copied().unwrap_or_default();
+    let end = series.last().copied().unwrap_or_default();
+
+    let mut growth = 1f64;
+    let mut held = false;
+    for step in series.windows(2) {
+        if step[0].value <= Decimal::default() {
+            continue;
+        }
+        held = true;
+        growth *= 1.0 + f64::from(step[1].gain() - step[0].gain()) / f64::from(step[0].value);
+    }
+    Performance {
+        period,
+        from,
+        to,
+        start_value: start.value,
+        end_value: end.value,
+        gain: end.gain() - start.gain(),
+        twr: held.then(|| Decimal::from_f64((growth - 1.0) * 100.0)),
+        income: end.paid - start.paid,
+    }
+}
+
+/// Dates from `from` to `to` every `step`.
+pub fn sample_dates(
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    step: Duration,
+) -> Result<Vec<NaiveDateTime>, Error> {
+    if step <= Duration::zero() {
+        return Err(Error::msg("Step must be positive"));
+    }
+    let mut dates = Vec::new();
+    let mut date = from;
+    while date <= to {
+        dates.push(date);
+        date += step;
+    }
+    Ok(dates)
+}
+
+/// Valuation of the assets at every step from `from` to `to`.
+pub fn value_series(
+    assets: &[(&AssetHistory, Decimal)],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    step: Duration,
+) -> Result<Vec<(NaiveDateTime, Valuation)>, Error> {
+    let dates = sample_dates(from, to, step)?;
+    Ok(dates
+        .into_iter()
+        .map(|date| (date, valuation(assets, date)))
+        .collect())
+}
+
+/// Valuation of the assets taken with the weights in percent.
+pub fn valuation(assets: &[(&AssetHistory, Decimal)], date: NaiveDateTime) -> Valuation {
+    let mut total = Valuation::default();
+    for (asset, weight) in assets {
+        total += asset.valuation(date).scaled(*weight);
+    }
+    total
+}
+
+/// Performance for every period, per asset, per type of one classification and of the
+/// whole portfolio.
+#[derive(Clone)]
+pub struct PerformanceTable {
+    pub periods: Vec<Period>,
+    pub assets: Vec<(Asset, Vec<Performance>)>,
+    pub types: Vec<(AssetType, Vec<Performance>)>,
+    pub total: Vec<Performance>,
+}
+
+impl Display for Performance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let twr = self
+            .twr
+            .map(|twr| format!("{}%", twr))
+            .unwrap_or("-".to_string());
+        write!(
+            f,
+            "{}: gain {}, twr {}, income {}",
+            self.period, self.gain, twr, self.income
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::model::operations::OperationType;
+    use crate::service::decimal::dec;
+    use crate::service::test_utils::{history, op, time};
+
+    #[test]
+    fn test_period_range() {
+        let now = time("2023-08-15 12:00:00");
+        let inception = time("2021-03-01 00:00:00");
+        let range = |period: Period| period.range(now, inception).0;
+        assert_eq!(range(Period::MonthToDate), time("2023-08-01 00:00:00"));
+        assert_eq!(range(Period::QuarterToDate), time("2023-07-01 00:00:00"));
+        assert_eq!(range(Period::YearToDate), time("2023-01-01 00:00:00"));
+        assert_eq!(range(Period::Years(3)), time("2020-08-15 12:00:00"));
+        assert_eq!(range(Period::SinceInception), inception);
+    }
+
+    #[test]
+    fn test_performance() {
+        // Price doubles, then the position doubles with new money and falls by a quarter.
+        let asset = history(vec![
+            op(OperationType::UpdatePrice, "100", "2023-01-01 00:00:00", 1),
+            op(OperationType::Buy, "10", "2023-01-01 00:00:00", 1),
+            op(OperationType::UpdatePrice, "200", "2023-03-01 00:00:00", 1),
+            op(OperationType::Buy, "10", "2023-03-01 00:00:00", 1),
+            op(OperationType::Dividend, "40", "2023-04-01 00:00:00", 1),
+            op(OperationType::UpdatePrice, "150", "2023-05-01 00:00:00", 1),
+        ]);
+        let now = time("2023-06-01 00:00:00");
+
+        let ytd = performance(&[(&asset, dec("100"))], Period::YearToDate, now);
+        assert_eq!(ytd.start_value, dec("1000"));
+        assert_eq!(ytd.end_value, dec("3000"));
+        assert_eq!(ytd.gain, dec("40"));
+        assert_eq!(ytd.income, dec("40"));
+        // 2 * 1.01 * 0.75 - 1
+        assert_eq!(ytd.twr, Some(dec("51.5")));
+
+        let half = performance(
+            &[(&asset, dec("50"))],
+            Period::Custom(time("2023-04-15 00:00:00"), now),
+            now,
+        );
+        assert_eq!(half.start_value, dec("2000"));
+        assert_eq!(half.gain, dec("-500"));
+        assert_eq!(half.twr, Some(dec("-25")));
+
+        let empty = performance(&[], Period::MonthToDate, now);
+        assert_eq!(empty.twr, None);
+    }
+
+    #[test]
+    fn test_value_series() {
+        let asset = history(vec![
+            op(OperationType::UpdatePrice, "100", "2023-01-01 00:00:00", 1),
+            op(OperationType::Buy, "10", "2023-01-01 00:00:00", 1),
+            op(OperationType::UpdatePrice, "110", "2023-01-02 00:00:00", 1),
+        ]);
+        let assets = [(&asset, dec("100"))];
+        let (from, to) = (time("2023-01-01 00:00:00"), time("2023-01-02 12:00:00"));
+        let series = value_series(&assets, from, to, Duration::days(1)).unwrap();
+        let values = series.iter().map(|(_, v)| v.value).collect::<Vec<_>>();
+        assert_eq!(values, vec![dec("1000"), dec("1100")]);
+
+        assert!(value_series(&assets, from, to, Duration::zero()).is_err());
+        assert!(value_series(&assets, from, to, Duration::days(-1)).is_err());
+    }
+
+    #[test]
+    fn test_sample_dates() {
+        let (from, to) = (time("2023-01-01 00:00:00"), time("2023-01-02 12:00:00"));
+        assert_eq!(
+            sample_dates(from, to, Duration::hours(12)).unwrap(),
+            vec![
+                from,
+                time("2023-01-01 12:00:00"),
+                time("2023-01-02 00:00:00"),
+                to
+            ]
+        );
+        assert_eq!(sample_dates(to, from, Duration::days(1)).unwrap(), vec![]);
+        assert!(sample_dates(from, to, Duration::zero()).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::dao::model::assets::{Asset, AssetToType, AssetType};
+use crate::dao::model::operations::OperationType;
+use crate::dao::model::target::TargetAllocation;
+use crate::service::category::subtree;
+use crate::service::decimal::Decimal;
+use std::collections::HashMap;
+
+/// Position which can be traded.
+#[derive(Clone)]
+pub struct Holding {
+    pub asset: Asset,
+    pub quantity: Decimal,
+    /// Unit price in the base currency.
+    pub price: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RebalanceOptions {
+    /// New money added to the cash in the account currency, in the base currency.
+    pub contribution: Decimal,
+    /// Trades of a smaller value are skipped, in the base currency.
+    pub min_trade: Decimal,
+    /// Only buy with the cash and the contribution.
+    pub no_sell: bool,
+}
+
+/// Operation proposed by the rebalancing, see [crate::service::FinanceService::add_operation].
+#[derive(Clone)]
+pub struct DraftOperation {
+    pub asset: Asset,
+    /// [OperationType::Buy] or [OperationType::Sell].
+    pub operation_type: OperationType,
+    pub amount: Decimal,
+    /// Trade value in the base currency.
+    pub value: Decimal,
+}
+
+#[derive(Clone, Default)]
+pub struct RebalancePlan {
+    /// Sells go first, so their proceeds fund the buys.
+    pub operations: Vec<DraftOperation>,
+    /// Cash left after the operations, in the base currency.
+    pub cash_left: Decimal,
+    /// Targeted types without an asset to trade.
+    pub untradable: Vec<AssetType>,
+}
+
+/// Proposes trades bringing the holdings to the targets of the classification `types`.
+///
+/// Only assets classified entirely within one targeted type are traded. Other assets keep
+/// their value, their weighted share is subtracted from the targets. The delta of a type is
+/// split between its assets proportionally to their value, or equally if none is held.
+///
+/// `cash` holds the balance of every currency id in the base currency. Assets are bought
+/// with the cash in their currency only, currency exchanges are not proposed.
+pub fn rebalance(
+    types: &[AssetType],
+    links: &[AssetToType],
+    targets: &[TargetAllocation],
+    holdings: &[Holding],
+    cash: &HashMap<i64, Decimal>,
+    account_currency: i64,
+    options: &RebalanceOptions,
+) -> RebalancePlan {
+    let zero = Decimal::default();
+    let hundred = Decimal::int(100);
+    let targets = targets
+        .iter()
+        .filter(|t| types.iter().any(|tp| tp.id == t.type_id))
+        .collect::<Vec<_>>();
+    let value = |h: &Holding| h.quantity * h.price;
+    let total = holdings.iter().fold(
+        cash.values().fold(options.contribution, |acc, c| acc + *c),
+        |acc, h| acc + value(h),
+    );
+
+    let mut plan = RebalancePlan::default();
+    let mut deltas = Vec::<(&Holding, Decimal)>::new();
+    for target in &targets {
+        let ids = subtree(types, target.type_id);
+        // Nested targets are reached through the leaf ones.
+        if targets
+            .iter()
+            .any(|t| t.type_id != target.type_id && ids.contains(&t.type_id))
+        {
+            continue;
+        }
+
+        let mut pure = Vec::new();
+        let mut fixed = zero;
+        for holding in holdings {
+            let asset_links = links
+                .iter()
+                .filter(|l| l.asset_id == holding.asset.id)
+                .filter(|l| types.iter().any(|t| t.id == l.type_id))
+                .collect::<Vec<_>>();
+            let weight = asset_links
+                .iter()
+                .filter(|l| ids.contains(&l.type_id))
+                .fold(zero, |acc, l| acc + l.weight);
+            if weight >= hundred && asset_links.iter().all(|l| ids.contains(&l.type_id)) {
+                pure.push(holding);
+            } else {
+                fixed += value(holding) * weight.min(hundred) / hundred;
+            }
+        }
+        let tradable = pure
+            .into_iter()
+            .filter(|h| h.price > zero)
+            .collect::<Vec<_>>();
+        if tradable.is_empty() {
+            if let Some(tp) = types.iter().find(|t| t.id == target.type_id) {
+                plan.untradable.push(tp.clone());
+            }
+            continue;
+        }
+
+        let desired = (total * target.weight / hundred - fixed).max(zero);
+        let current = tradable.iter().fold(zero, |acc, h| acc + value(h));
+        let count = Decimal::int(tradable.len() as i64);
+        for holding in tradable {
+            let desired = if current > zero {
+                desired * value(holding) / current
+            } else {
+                desired / count
+            };
+            deltas.push((holding, desired - value(holding)));
+        }
+    }
+
+    let mut available = cash.clone();
+    *available.entry(account_currency).or_default() += options.contribution;
+    if !options.no_sell {
+        for (holding, delta) in deltas.iter().filter(|(_, d)| *d < zero) {
+            let amount = round_to_lot((zero - *delta) / holding.price, holding.asset.lot_size)
+                .min(holding.quantity);
+            let value = amount * holding.price;
+            if amount <= zero || value < options.min_trade {
+                continue;
+            }
+            *available.entry(holding.asset.currency).or_default() += value;
+            plan.operations.push(DraftOperation {
+                asset: holding.asset.clone(),
+                operation_type: OperationType::Sell,
+                amount,
+                value,
+            });
+        }
+    }
+
+    let mut buys = HashMap::<i64, Decimal>::new();
+    for (holding, delta) in deltas.iter().filter(|(_, d)| *d > zero) {
+        *buys.entry(holding.asset.currency).or_default() += *delta;
+    }
+    let budget = available.clone();
+    for (holding, delta) in deltas.iter().filter(|(_, d)| *d > zero) {
+        let currency = holding.asset.currency;
+        let buys = buys[&currency];
+        let budget = budget.get(&currency).copied().unwrap_or_default().max(zero);
+        let delta = if buys > budget {
+            *delta * budget / buys
+        } else {
+            *delta
+        };
+        let lot = lot_or_min(holding.asset.lot_size);
+        let cash = available.entry(currency).or_default();
+        let mut amount = round_to_lot(delta / holding.price, lot);
+        while amount > zero && amount * holding.price > *cash {
+            amount -= lot;
+        }
+        let value = amount * holding.price;
+        if amount <= zero || value < options.min_trade {
+            continue;
+        }
+        *cash -= value;
+        plan.operations.push(DraftOperation {
+            asset: holding.asset.clone(),
+            operation_type: OperationType::Buy,
+            amount,
+            value,
+        });
+    }
+    plan.cash_left = available.values().fold(zero, |acc, c| acc + *c);
+    plan
+}
+
+/// Lot size, or the smallest decimal step if it is not set.
+fn lot_or_min(lot: Decimal) -> Decimal {
+    if lot > Decimal::default() {
+        lot
+    } else {
+        Decimal::from(1)
+    }
+}
+
+/// Rounds the quantity down to a multiple of the lot size.
+fn round_to_lot(quantity: Decimal, lot: Decimal) -> Decimal {
+    let lot: i64 = lot_or_min(lot).into();
+    let quantity: i64 = quantity.into();
+    Decimal::from(quantity / lot * lot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+    use crate::service::test_utils::{link, target, tp};
+
+    fn holding(id: i64, quantity: &str, price: &str, lot: &str) -> Holding {
+        Holding {
+            asset: Asset {
+                id,
+                ticker: id.to_string(),
+                lot_size: dec(lot),
+                ..Default::default()
+            },
+            quantity: dec(quantity),
+            price: dec(price),
+        }
+    }
+
+    fn cash(value: &str) -> HashMap<i64, Decimal> {
+        HashMap::from([(0, dec(value))])
+    }
+
+    fn trades(plan: &RebalancePlan) -> Vec<(i64, OperationType, Decimal)> {
+        plan.operations
+            .iter()
+            .map(|op| (op.asset.id, op.operation_type, op.amount))
+            .collect()
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let types = vec![tp(1, None), tp(2, None), tp(3, None)];
+        let links = vec![link(10, 1, "100"), link(11, 2, "100")];
+        let targets = vec![target(1, "60"), target(2, "40"), target(3, "0")];
+        let holdings = vec![holding(10, "70", "10", "1"), holding(11, "30", "10", "1")];
+
+        let plan = rebalance(
+            &types,
+            &links,
+            &targets,
+            &holdings,
+            &cash("0"),
+            0,
+            &Default::default(),
+        );
+        assert_eq!(
+            trades(&plan),
+            vec![
+                (10, OperationType::Sell, dec("10")),
+                (11, OperationType::Buy, dec("10")),
+            ]
+        );
+        assert_eq!(plan.cash_left, dec("0"));
+        assert_eq!(plan.untradable, vec![tp(3, None)]);
+
+        let options = RebalanceOptions {
+            contribution: dec("100"),
+            no_sell: true,
+            ..Default::default()
+        };
+        let plan = rebalance(&types, &links, &targets, &holdings, &cash("0"), 0, &options);
+        assert_eq!(trades(&plan), vec![(11, OperationType::Buy, dec("10"))]);
+
+        let options = RebalanceOptions {
+            min_trade: dec("150"),
+            ..Default::default()
+        };
+        let plan = rebalance(&types, &links, &targets, &holdings, &cash("0"), 0, &options);
+        assert!(plan.operations.is_empty());
+    }
+
+    #[test]
+    fn test_rebalance_lots_and_funds() {
+        let types = vec![tp(1, None), tp(2, None)];
+        // Balanced fund is half stocks, half bonds and is not traded.
+        let links = vec![
+            link(10, 1, "100"),
+            link(11, 2, "100"),
+            link(12, 1, "50"),
+            link(12, 2, "50"),
+        ];
+        let targets = vec![target(1, "50"), target(2, "50")];
+        let holdings = vec![
+            holding(10, "60", "10", "1"),
+            holding(11, "20", "10", "3"),
+            holding(12, "2", "100", "1"),
+        ];
+
+        let options = RebalanceOptions {
+            no_sell: true,
+            ..Default::default()
+        };
+        let plan = rebalance(
+            &types,
+            &links,
+            &targets,
+            &holdings,
+            &cash("110"),
+            0,
+            &options,
+        );
+        // Total 1110: bonds target 555, fund covers 100, so 455 are needed and 200 are held.
+        assert_eq!(trades(&plan), vec![(11, OperationType::Buy, dec("9"))]);
+        assert_eq!(plan.cash_left, dec("20"));
+    }
+
+    #[test]
+    fn test_rebalance_currencies() {
+        let types = vec![tp(1, None), tp(2, None)];
+        let links = vec![link(10, 1, "100"), link(11, 2, "100")];
+        let targets = vec![target(1, "50"), target(2, "50")];
+        let mut foreign = holding(11, "0", "10", "1");
+        foreign.asset.currency = 1;
+        let holdings = vec![holding(10, "0", "10", "1"), foreign];
+
+        let options = RebalanceOptions {
+            no_sell: true,
+            ..Default::default()
+        };
+        // Only the cash of the asset currency is spent.
+        let cash = HashMap::from([(0, dec("100")), (1, dec("30"))]);
+        let plan = rebalance(&types, &links, &targets, &holdings, &cash, 0, &options);
+        assert_eq!(
+            trades(&plan),
+            vec![
+                (10, OperationType::Buy, dec("6")),
+                (11, OperationType::Buy, dec("3")),
+            ]
+        );
+        assert_eq!(plan.cash_left, dec("40"));
+    }
+}
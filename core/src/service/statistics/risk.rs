@@ -0,0 +1,257 @@
+use crate::service::decimal::Decimal;
+use crate::service::statistics::performance::Valuation;
+use chrono::{Duration, NaiveDateTime};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Annual risk-free rate, in percent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskFree {
+    Fixed(Decimal),
+    /// Ticker of a stored rate series, rates are read as annual percents.
+    Series(String),
+}
+
+impl Default for RiskFree {
+    fn default() -> Self {
+        RiskFree::Fixed(Decimal::default())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RiskOptions {
+    pub risk_free: RiskFree,
+    /// Asset to compute the beta against.
+    pub benchmark: Option<String>,
+}
+
+/// Largest fall of the cumulative return from a peak.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drawdown {
+    /// Fall from the peak, in percent.
+    pub depth: Decimal,
+    pub peak: NaiveDateTime,
+    pub trough: NaiveDateTime,
+    /// First date back at the peak, `None` if not recovered yet.
+    pub recovery: Option<NaiveDateTime>,
+}
+
+/// `None` where the series is too short or flat.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RiskMetrics {
+    /// Annualized standard deviation of the returns, in percent.
+    pub volatility: Option<Decimal>,
+    pub max_drawdown: Option<Drawdown>,
+    pub sharpe: Option<Decimal>,
+    pub sortino: Option<Decimal>,
+    pub beta: Option<Decimal>,
+}
+
+/// Return of every step of the series ending at the step date, flows excluded.
+/// Steps starting without value are skipped.
+pub fn returns(series: &[(NaiveDateTime, Valuation)]) -> Vec<(NaiveDateTime, f64)> {
+    series
+        .windows(2)
+        .filter(|step| step[0].1.value > Decimal::default())
+        .map(|step| {
+            let gain = f64::from(step[1].1.gain() - step[0].1.gain());
+            (step[1].0, gain / f64::from(step[0].1.value))
+        })
+        .collect()
+}
+
+/// Number of `step` periods in a year.
+pub fn periods_per_year(step: Duration) -> f64 {
+    Duration::days(365).num_seconds() as f64 / step.num_seconds() as f64
+}
+
+/// Converts an annual rate in percent to the rate of one of `periods_per_year` periods.
+pub fn periodic_rate(annual: Decimal, periods_per_year: f64) -> f64 {
+    (1.0 + f64::from(annual) / 100.0).powf(1.0 / periods_per_year) - 1.0
+}
+
+/// Risk metrics of the returns. `risk_free` holds the rate of each return period,
+/// `benchmark` returns are matched by date.
+pub fn risk(
+    returns: &[(NaiveDateTime, f64)],
+    risk_free: &[f64],
+    benchmark: Option<&[(NaiveDateTime, f64)]>,
+    periods_per_year: f64,
+) -> RiskMetrics {
+    let values = returns.iter().map(|(_, r)| *r).collect::<Vec<_>>();
+    let excess = values
+        .iter()
+        .zip(risk_free.iter().chain(std::iter::repeat(&0.0)))
+        .map(|(r, rf)| r - rf)
+        .collect::<Vec<_>>();
+    let annual = periods_per_year.sqrt();
+    let ratio = |num: f64, den: f64| (den > 0.0).then(|| Decimal::from_f64(num / den * annual));
+
+    let deviation = std_dev(&values);
+    let downside = if excess.is_empty() {
+        None
+    } else {
+        let squares = excess.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>();
+        Some((squares / excess.len() as f64).sqrt())
+    };
+    let beta = benchmark.and_then(|benchmark| {
+        let benchmark = benchmark.iter().copied().collect::<HashMap<_, _>>();
+        let (own, other): (Vec<f64>, Vec<f64>) = returns
+            .iter()
+            .filter_map(|(date, r)| benchmark.get(date).map(|b| (*r, *b)))
+            .unzip();
+        let variance = covariance(&other, &other).filter(|v| *v > 0.0)?;
+        covariance(&own, &other).map(|c| Decimal::from_f64(c / variance))
+    });
+    RiskMetrics {
+        volatility: deviation.map(|d| Decimal::from_f64(d * annual * 100.0)),
+        max_drawdown: max_drawdown(returns),
+        sharpe: deviation.and_then(|d| ratio(mean(&excess)?, d)),
+        sortino: downside.and_then(|d| ratio(mean(&excess)?, d)),
+        beta,
+    }
+}
+
+/// Drawdown of the cumulative return, dates are the ends of the return periods.
+fn max_drawdown(returns: &[(NaiveDateTime, f64)]) -> Option<Drawdown> {
+    let mut index = 1.0;
+    let mut peak = (returns.first()?.0, 1.0);
+    let mut worst: Option<(f64, NaiveDateTime, NaiveDateTime, f64)> = None;
+    let mut recovery = None;
+    for (date, r) in returns {
+        index *= 1.0 + r;
+        if index >= peak.1 {
+            if let Some((_, _, _, level)) = worst {
+                if recovery.is_none() && index >= level {
+                    recovery = Some(*date);
+                }
+            }
+            peak = (*date, index);
+            continue;
+        }
+        let depth = 1.0 - index / peak.1;
+        if worst.is_none_or(|(max, ..)| depth > max) {
+            worst = Some((depth, peak.0, *date, peak.1));
+            recovery = None;
+        }
+    }
+    let (depth, peak, trough, _) = worst?;
+    Some(Drawdown {
+        depth: Decimal::from_f64(depth * 100.0),
+        peak,
+        trough,
+        recovery,
+    })
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Sample covariance.
+pub fn covariance(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() < 2 || a.len() != b.len() {
+        return None;
+    }
+    let (mean_a, mean_b) = (mean(a)?, mean(b)?);
+    let sum = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| (a - mean_a) * (b - mean_b))
+        .sum::<f64>();
+    Some(sum / (a.len() - 1) as f64)
+}
+
+/// Sample standard deviation.
+pub fn std_dev(values: &[f64]) -> Option<f64> {
+    covariance(values, values).map(f64::sqrt)
+}
+
+impl Display for RiskMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |val: Option<Decimal>| val.map(|v| v.to_string()).unwrap_or("-".to_string());
+        writeln!(f, "volatility: {}%", show(self.volatility))?;
+        match &self.max_drawdown {
+            Some(drawdown) => writeln!(
+                f,
+                "max drawdown: {}% ({} - {}, recovered {})",
+                drawdown.depth,
+                drawdown.peak,
+                drawdown.trough,
+                drawdown
+                    .recovery
+                    .map(|d| d.to_string())
+                    .unwrap_or("-".to_string())
+            )?,
+            None => writeln!(f, "max drawdown: -")?,
+        }
+        writeln!(f, "sharpe: {}", show(self.sharpe))?;
+        writeln!(f, "sortino: {}", show(self.sortino))?;
+        writeln!(f, "beta: {}", show(self.beta))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+
+    fn series(values: &[f64]) -> Vec<(NaiveDateTime, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (NaiveDateTime::default() + Duration::days(i as i64), *r))
+            .collect()
+    }
+
+    #[test]
+    fn test_returns() {
+        let point = |day: i64, value: &str, invested: &str| {
+            (
+                NaiveDateTime::default() + Duration::days(day),
+                Valuation {
+                    value: dec(value),
+                    invested: dec(invested),
+                    ..Default::default()
+                },
+            )
+        };
+        let returns = returns(&[
+            point(0, "0", "0"),
+            point(1, "100", "100"),
+            point(2, "110", "100"),
+            // New money does not count as a return.
+            point(3, "220", "200"),
+        ]);
+        let values = returns.iter().map(|(_, r)| *r).collect::<Vec<_>>();
+        assert_eq!(values.len(), 2);
+        assert!((values[0] - 0.1).abs() < 1e-9);
+        assert!((values[1] - 1.0 / 11.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_risk() {
+        let returns = series(&[0.1, -0.2, 0.05, 0.2, -0.1]);
+        let benchmark = series(&[0.05, -0.1, 0.025, 0.1, -0.05]);
+        let metrics = risk(&returns, &[0.0; 5], Some(&benchmark), 4.0);
+        // Sample deviation 0.1597, annualized by sqrt(4).
+        assert_eq!(metrics.volatility, Some(dec("31.94")));
+        assert_eq!(metrics.sharpe, Some(dec("0.13")));
+        assert_eq!(metrics.sortino, Some(dec("0.2")));
+        assert_eq!(metrics.beta, Some(dec("2")));
+
+        let drawdown = metrics.max_drawdown.unwrap();
+        assert_eq!(drawdown.depth, dec("20"));
+        assert_eq!(drawdown.peak, returns[0].0);
+        assert_eq!(drawdown.trough, returns[1].0);
+        assert_eq!(drawdown.recovery, Some(returns[3].0));
+
+        assert_eq!(risk(&[], &[], None, 12.0), RiskMetrics::default());
+    }
+
+    #[test]
+    fn test_periodic_rate() {
+        assert!((periodic_rate(dec("21"), 2.0) - 0.1).abs() < 1e-9);
+    }
+}
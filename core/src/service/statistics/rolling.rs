@@ -0,0 +1,197 @@
+use crate::service::decimal::Decimal;
+use crate::service::statistics::currency::{CurrencyGraph, Point};
+use crate::service::statistics::risk::std_dev;
+use std::fmt::Display;
+
+/// Statistic over a window of the last points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    /// Simple moving average.
+    Sma(usize),
+    /// Exponential moving average, seeded with the simple one.
+    Ema(usize),
+    Min(usize),
+    Max(usize),
+    /// Change from the point `window` steps back, in percent.
+    Return(usize),
+    /// Standard deviation of the point to point returns in the window, in percent.
+    Volatility(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedSeries {
+    pub name: String,
+    pub points: Vec<Point>,
+}
+
+/// Graph with the overlays computed on its points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlayGraph {
+    pub ticker: String,
+    pub points: Vec<Point>,
+    pub overlays: Vec<NamedSeries>,
+}
+
+impl Overlay {
+    pub fn window(&self) -> usize {
+        match *self {
+            Overlay::Sma(window)
+            | Overlay::Ema(window)
+            | Overlay::Min(window)
+            | Overlay::Max(window)
+            | Overlay::Return(window)
+            | Overlay::Volatility(window) => window,
+        }
+    }
+
+    /// Values from the first point with a full window.
+    pub fn apply(&self, points: &[Point]) -> NamedSeries {
+        let window = self.window().max(1);
+        let values = points
+            .iter()
+            .map(|p| f64::from(p.value))
+            .collect::<Vec<_>>();
+        let mut result = Vec::new();
+        let mut ema = None;
+        for end in window..=values.len() {
+            let slice = &values[end - window..end];
+            let value = match self {
+                Overlay::Sma(_) => Some(mean(slice)),
+                Overlay::Ema(_) => {
+                    let alpha = 2.0 / (window as f64 + 1.0);
+                    let next = match ema {
+                        None => mean(slice),
+                        Some(prev) => prev + alpha * (slice[window - 1] - prev),
+                    };
+                    ema = Some(next);
+                    Some(next)
+                }
+                Overlay::Min(_) => slice.iter().copied().reduce(f64::min),
+                Overlay::Max(_) => slice.iter().copied().reduce(f64::max),
+                Overlay::Return(_) => (end > window && values[end - window - 1] != 0.0)
+                    .then(|| (slice[window - 1] / values[end - window - 1] - 1.0) * 100.0),
+                Overlay::Volatility(_) => {
+                    let returns = slice
+                        .windows(2)
+                        .filter(|step| step[0] != 0.0)
+                        .map(|step| step[1] / step[0] - 1.0)
+                        .collect::<Vec<_>>();
+                    std_dev(&returns).map(|d| d * 100.0)
+                }
+            };
+            if let Some(value) = value {
+                result.push(Point {
+                    date: points[end - 1].date,
+                    value: Decimal::from_f64(value),
+                });
+            }
+        }
+        NamedSeries {
+            name: self.to_string(),
+            points: result,
+        }
+    }
+}
+
+/// Adds the overlays to the graph.
+pub fn with_overlays(graph: CurrencyGraph, overlays: &[Overlay]) -> OverlayGraph {
+    OverlayGraph {
+        overlays: overlays.iter().map(|o| o.apply(&graph.points)).collect(),
+        ticker: graph.ticker,
+        points: graph.points,
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+impl Display for Overlay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Overlay::Sma(window) => write!(f, "SMA({})", window),
+            Overlay::Ema(window) => write!(f, "EMA({})", window),
+            Overlay::Min(window) => write!(f, "MIN({})", window),
+            Overlay::Max(window) => write!(f, "MAX({})", window),
+            Overlay::Return(window) => write!(f, "RETURN({})", window),
+            Overlay::Volatility(window) => write!(f, "VOLATILITY({})", window),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+    use chrono::{Duration, NaiveDateTime};
+
+    fn points(values: &[&str]) -> Vec<Point> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| Point {
+                date: NaiveDateTime::default() + Duration::days(i as i64),
+                value: dec(v),
+            })
+            .collect()
+    }
+
+    fn values(series: &NamedSeries) -> Vec<Decimal> {
+        series.points.iter().map(|p| p.value).collect()
+    }
+
+    #[test]
+    fn test_overlays() {
+        let points = points(&["10", "12", "11", "14", "13"]);
+        let graph = with_overlays(
+            CurrencyGraph {
+                ticker: "USD".to_string(),
+                points: points.clone(),
+            },
+            &[
+                Overlay::Sma(3),
+                Overlay::Ema(3),
+                Overlay::Min(2),
+                Overlay::Max(2),
+                Overlay::Return(2),
+            ],
+        );
+        assert_eq!(graph.points, points);
+        let names = graph
+            .overlays
+            .iter()
+            .map(|o| o.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["SMA(3)", "EMA(3)", "MIN(2)", "MAX(2)", "RETURN(2)"]
+        );
+
+        let sma = &graph.overlays[0];
+        assert_eq!(sma.points[0].date, points[2].date);
+        assert_eq!(values(sma), vec![dec("11"), dec("12.33"), dec("12.67")]);
+        assert_eq!(
+            values(&graph.overlays[1]),
+            vec![dec("11"), dec("12.5"), dec("12.75")]
+        );
+        assert_eq!(
+            values(&graph.overlays[2]),
+            vec![dec("10"), dec("11"), dec("11"), dec("13")]
+        );
+        assert_eq!(
+            values(&graph.overlays[3]),
+            vec![dec("12"), dec("12"), dec("14"), dec("14")]
+        );
+        assert_eq!(
+            values(&graph.overlays[4]),
+            vec![dec("10"), dec("16.67"), dec("18.18")]
+        );
+    }
+
+    #[test]
+    fn test_volatility() {
+        let series = Overlay::Volatility(3).apply(&points(&["100", "110", "99", "108.9"]));
+        // Returns 10%, -10% and 10%.
+        assert_eq!(values(&series), vec![dec("14.14"), dec("14.14")]);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::dao::model::assets::AssetType;
+use crate::dao::model::target::TargetAllocation;
+use crate::service::category::ancestors;
+use crate::service::decimal::Decimal;
+use crate::service::statistics::types::Breakdown;
+use color_eyre::eyre::Error;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Current share of a type against its target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetDrift {
+    pub tp: AssetType,
+    /// Target share, in percent.
+    pub target: Decimal,
+    /// Current share, in percent.
+    pub current: Decimal,
+    pub value: Decimal,
+    pub target_value: Decimal,
+    /// Current minus target share, in percentage points.
+    pub drift: Decimal,
+    /// Drift relative to the target, in percent. `None` for a zero target.
+    pub relative_drift: Option<Decimal>,
+    pub band: Decimal,
+    /// Drift is outside of the band.
+    pub out_of_band: bool,
+}
+
+/// Compares the breakdown with the targets of its classification.
+pub fn drift(breakdown: &Breakdown, targets: &[TargetAllocation]) -> Vec<TargetDrift> {
+    let hundred = Decimal::int(100);
+    breakdown
+        .types
+        .iter()
+        .filter_map(|alloc| {
+            let target = targets.iter().find(|t| t.type_id == alloc.tp.id)?;
+            let drift = alloc.share - target.weight;
+            let relative_drift = if target.weight > Decimal::default() {
+                let (drift_raw, target_raw): (i64, i64) = (drift.into(), target.weight.into());
+                Some(Decimal::from_ratio(
+                    drift_raw as i128 * 100,
+                    target_raw as i128,
+                ))
+            } else {
+                None
+            };
+            let abs_drift = if drift < Decimal::default() {
+                Decimal::default() - drift
+            } else {
+                drift
+            };
+            Some(TargetDrift {
+                tp: alloc.tp.clone(),
+                target: target.weight,
+                current: alloc.share,
+                value: alloc.value,
+                target_value: breakdown.total * target.weight / hundred,
+                drift,
+                relative_drift,
+                band: target.band,
+                out_of_band: abs_drift > target.band,
+            })
+        })
+        .collect()
+}
+
+/// Checks that targets of the child types fit into the target of the nearest targeted ancestor,
+/// and that top level targets of each classification sum to at most 100%.
+pub fn check_targets(types: &[AssetType], targets: &[TargetAllocation]) -> Result<(), Error> {
+    let hundred = Decimal::int(100);
+    let weights = targets
+        .iter()
+        .map(|t| (t.type_id, t.weight))
+        .collect::<HashMap<_, _>>();
+    // Children sums keyed by the targeted ancestor, or by the classification for top level targets.
+    let mut sums = HashMap::<(i64, Option<i64>), Decimal>::new();
+    for target in targets {
+        if target.weight < Decimal::default() || target.weight > hundred {
+            return Err(Error::msg(format!(
+                "Target must be in [0, 100]: {}",
+                target.weight
+            )));
+        }
+        let tp = types
+            .iter()
+            .find(|t| t.id == target.type_id)
+            .ok_or(Error::msg("Type not found"))?;
+        let parent = ancestors(types, tp.id)
+            .into_iter()
+            .find(|id| weights.contains_key(id));
+        *sums.entry((tp.classification, parent)).or_default() += target.weight;
+    }
+    for ((_, parent), sum) in sums {
+        let limit = parent
+            .and_then(|p| weights.get(&p).copied())
+            .unwrap_or(hundred);
+        if sum > limit {
+            return Err(Error::msg(format!(
+                "Targets sum to {}% which exceeds {}%",
+                sum, limit
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl Display for TargetDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}% (target {}%, drift {}pp, band {}pp)",
+            self.tp.name, self.current, self.target, self.drift, self.band
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::model::assets::Classification;
+    use crate::service::decimal::dec;
+    use crate::service::statistics::types::TypeAllocation;
+    use crate::service::test_utils::{target, tp};
+
+    #[test]
+    fn test_drift() {
+        let alloc = |id: i64, value: &str, share: &str| TypeAllocation {
+            tp: tp(id, None),
+            value: dec(value),
+            share: dec(share),
+        };
+        let breakdown = Breakdown {
+            classification: Classification {
+                id: 1,
+                name: "class".to_string(),
+                description: "".to_string(),
+            },
+            types: vec![
+                alloc(1, "700", "70"),
+                alloc(2, "280", "28"),
+                alloc(3, "20", "2"),
+            ],
+            unclassified: dec("0"),
+            total: dec("1000"),
+        };
+        let drift = drift(&breakdown, &[target(1, "60"), target(2, "40")]);
+        assert_eq!(drift.len(), 2);
+        assert_eq!(drift[0].drift, dec("10"));
+        assert_eq!(drift[0].relative_drift, Some(dec("16.67")));
+        assert_eq!(drift[0].target_value, dec("600"));
+        assert!(drift[0].out_of_band);
+        assert_eq!(drift[1].drift, dec("-12"));
+        assert_eq!(drift[1].relative_drift, Some(dec("-30")));
+        assert!(drift[1].out_of_band);
+    }
+
+    #[test]
+    fn test_check_targets() {
+        let types = vec![tp(1, None), tp(2, Some(1)), tp(3, Some(1)), tp(4, None)];
+        assert!(
+            check_targets(&types, &[target(1, "60"), target(2, "40"), target(4, "40")]).is_ok()
+        );
+        assert!(check_targets(&types, &[target(1, "60"), target(4, "41")]).is_err());
+        assert!(
+            check_targets(&types, &[target(1, "60"), target(2, "40"), target(3, "21")]).is_err()
+        );
+        assert!(check_targets(&types, &[target(2, "70"), target(3, "30")]).is_ok());
+    }
+}
@@ -1 +1,166 @@
+use crate::dao::model::assets::{AssetToType, AssetType, Classification};
+use crate::service::category::subtree;
+use crate::service::decimal::Decimal;
+use std::collections::HashMap;
 
+/// Value of the assets in the type and all of its descendants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeAllocation {
+    pub tp: AssetType,
+    pub value: Decimal,
+    /// Share of the total value of all assets, in percent.
+    pub share: Decimal,
+}
+
+/// Portfolio breakdown by the types of one classification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakdown {
+    pub classification: Classification,
+    pub types: Vec<TypeAllocation>,
+    /// Value of the assets not classified in the classification.
+    pub unclassified: Decimal,
+    /// Value of all assets.
+    pub total: Decimal,
+}
+
+/// Rolls asset values up the type tree.
+/// Each asset contributes its value multiplied by the sum of its link weights
+/// within the subtree, capped at 100%.
+pub fn allocation(
+    types: &[AssetType],
+    links: &[AssetToType],
+    values: &HashMap<i64, Decimal>,
+) -> Vec<TypeAllocation> {
+    let total = sum(values);
+    types
+        .iter()
+        .map(|tp| {
+            let value = weighted_value(&subtree(types, tp.id), links, values);
+            let share = if total > Decimal::default() {
+                let (value_raw, total_raw): (i64, i64) = (value.into(), total.into());
+                Decimal::from_ratio(value_raw as i128 * 100, total_raw as i128)
+            } else {
+                Decimal::default()
+            };
+            TypeAllocation {
+                tp: tp.clone(),
+                value,
+                share,
+            }
+        })
+        .collect()
+}
+
+/// Splits asset values by the types of the classification.
+pub fn breakdown(
+    classification: Classification,
+    types: &[AssetType],
+    links: &[AssetToType],
+    values: &HashMap<i64, Decimal>,
+) -> Breakdown {
+    let types = types
+        .iter()
+        .filter(|t| t.classification == classification.id)
+        .cloned()
+        .collect::<Vec<_>>();
+    let ids = types.iter().map(|t| t.id).collect::<Vec<_>>();
+    let total = sum(values);
+    Breakdown {
+        classification,
+        types: allocation(&types, links, values),
+        unclassified: total - weighted_value(&ids, links, values),
+        total,
+    }
+}
+
+/// Share of each asset in the types, in percent: sum of the link weights capped at 100%.
+pub fn type_weights(ids: &[i64], links: &[AssetToType]) -> HashMap<i64, Decimal> {
+    let mut weights = HashMap::<i64, Decimal>::new();
+    for link in links.iter().filter(|link| ids.contains(&link.type_id)) {
+        *weights.entry(link.asset_id).or_default() += link.weight;
+    }
+    for weight in weights.values_mut() {
+        *weight = (*weight).min(Decimal::int(100));
+    }
+    weights
+}
+
+/// Value of the assets linked to the types, split by their weights.
+fn weighted_value(ids: &[i64], links: &[AssetToType], values: &HashMap<i64, Decimal>) -> Decimal {
+    type_weights(ids, links)
+        .iter()
+        .filter_map(|(asset, weight)| {
+            values
+                .get(asset)
+                .map(|val| *val * *weight / Decimal::int(100))
+        })
+        .fold(Decimal::default(), |acc, val| acc + val)
+}
+
+fn sum(values: &HashMap<i64, Decimal>) -> Decimal {
+    values
+        .values()
+        .fold(Decimal::default(), |acc, val| acc + *val)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::decimal::dec;
+    use crate::service::test_utils::{link, tp};
+
+    #[test]
+    fn test_allocation() {
+        let types = vec![tp(1, None), tp(2, Some(1)), tp(3, Some(2)), tp(4, None)];
+        let links = vec![
+            link(10, 3, "100"),
+            link(10, 2, "100"),
+            link(11, 1, "100"),
+            link(12, 4, "100"),
+        ];
+        let values = HashMap::from([(10, dec("300")), (11, dec("100")), (12, dec("600"))]);
+
+        let alloc = allocation(&types, &links, &values);
+        assert_eq!(alloc[0].value, dec("400"));
+        assert_eq!(alloc[0].share, dec("40"));
+        assert_eq!(alloc[1].value, dec("300"));
+        assert_eq!(alloc[2].value, dec("300"));
+        assert_eq!(alloc[3].share, dec("60"));
+    }
+
+    #[test]
+    fn test_weighted_allocation() {
+        // Balanced fund: 60% stocks, 40% bonds.
+        let types = vec![tp(1, None), tp(2, Some(1)), tp(3, Some(1))];
+        let links = vec![link(10, 2, "60"), link(10, 3, "40"), link(11, 2, "100")];
+        let values = HashMap::from([(10, dec("1000")), (11, dec("1000"))]);
+
+        let alloc = allocation(&types, &links, &values);
+        assert_eq!(alloc[0].value, dec("2000"));
+        assert_eq!(alloc[1].value, dec("1600"));
+        assert_eq!(alloc[1].share, dec("80"));
+        assert_eq!(alloc[2].value, dec("400"));
+    }
+
+    #[test]
+    fn test_breakdown() {
+        let region = AssetType {
+            classification: 2,
+            ..tp(4, None)
+        };
+        let types = vec![tp(1, None), tp(2, Some(1)), tp(3, Some(1)), region];
+        let links = vec![link(10, 2, "60"), link(10, 3, "30"), link(10, 4, "100")];
+        let values = HashMap::from([(10, dec("1000")), (11, dec("500"))]);
+        let classification = Classification {
+            id: 1,
+            name: "class".to_string(),
+            description: "".to_string(),
+        };
+
+        let breakdown = breakdown(classification, &types, &links, &values);
+        assert_eq!(breakdown.types.len(), 3);
+        assert_eq!(breakdown.types[0].value, dec("900"));
+        assert_eq!(breakdown.types[0].share, dec("60"));
+        assert_eq!(breakdown.unclassified, dec("600"));
+    }
+}
@@ -0,0 +1,46 @@
+use crate::dao::model::assets::AssetType;
+use crate::dao::model::target::TargetAllocation;
+use crate::dao::target::TargetDao;
+use crate::service::decimal::Decimal;
+use crate::service::statistics::target::check_targets;
+use color_eyre::eyre::Error;
+
+pub struct TargetService {
+    dao: TargetDao,
+}
+
+impl TargetService {
+    pub fn new(dao: TargetDao) -> Self {
+        Self { dao }
+    }
+
+    pub async fn list(&self) -> Result<Vec<TargetAllocation>, Error> {
+        self.dao.list().await
+    }
+
+    /// Sets the target of the type. `types` are all asset types, used to validate the target tree.
+    pub async fn set(
+        &self,
+        tp: &AssetType,
+        weight: Decimal,
+        band: Decimal,
+        types: &[AssetType],
+    ) -> Result<(), Error> {
+        if band <= Decimal::default() {
+            return Err(Error::msg("Drift band must be positive"));
+        }
+        let mut targets = self.list().await?;
+        targets.retain(|t| t.type_id != tp.id);
+        targets.push(TargetAllocation {
+            type_id: tp.id,
+            weight,
+            band,
+        });
+        check_targets(types, &targets)?;
+        self.dao.set(tp, weight, band).await
+    }
+
+    pub async fn remove(&self, tp: &AssetType) -> Result<(), Error> {
+        self.dao.remove(tp).await
+    }
+}
@@ -0,0 +1,80 @@
+use crate::dao::model::assets::{Asset, AssetToType, AssetType};
+use crate::dao::model::currency::{Currency, CurrencyRate};
+use crate::dao::model::operations::{AssetOperation, OperationType};
+use crate::dao::model::target::TargetAllocation;
+use crate::service::decimal::{self, dec, Rate};
+use crate::service::statistics::assets::AssetStatistic;
+use crate::service::statistics::currency::CurrencyStatistic;
+use crate::service::statistics::performance::AssetHistory;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+pub fn time(val: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(val, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+pub fn rate(id: i64, date: &str, rate: &str) -> CurrencyRate {
+    CurrencyRate {
+        id,
+        currency_id: 0,
+        rate: decimal::rate(rate),
+        date: time(date),
+        source: "manual".to_string(),
+        fetched_at: time(date),
+        version: 1,
+        superseded_at: None,
+    }
+}
+
+pub fn op(tp: OperationType, amount: &str, date: &str, currency_rate: i64) -> AssetOperation {
+    AssetOperation {
+        id: 0,
+        asset_id: 0,
+        operation_type: tp,
+        operation_date: time(date),
+        operation_amount: dec(amount),
+        currency_rate,
+        account: 0,
+    }
+}
+
+/// Asset history in the base currency, operations must use rate 1.
+pub fn history(ops: Vec<AssetOperation>) -> AssetHistory {
+    let currency = CurrencyStatistic::new(
+        Currency {
+            id: 0,
+            name: "USD".to_string(),
+            ticker: "USD".to_string(),
+        },
+        vec![rate(1, "2000-01-01 00:00:00", "1")],
+    );
+    let stat = AssetStatistic::new(Asset::default(), ops);
+    let base = stat.base_snapshots(&HashMap::from([(1, Rate::int(1))]), &currency);
+    AssetHistory::new(stat, base, currency)
+}
+
+pub fn tp(id: i64, parent: Option<i64>) -> AssetType {
+    AssetType {
+        id,
+        name: id.to_string(),
+        description: "".to_string(),
+        parent,
+        classification: 1,
+    }
+}
+
+pub fn link(asset_id: i64, type_id: i64, weight: &str) -> AssetToType {
+    AssetToType {
+        asset_id,
+        type_id,
+        weight: dec(weight),
+    }
+}
+
+pub fn target(type_id: i64, weight: &str) -> TargetAllocation {
+    TargetAllocation {
+        type_id,
+        weight: dec(weight),
+        band: dec("5"),
+    }
+}
@@ -0,0 +1,258 @@
+use crate::dao::model::operations::{AssetOperation, OperationType};
+use crate::service::decimal::Decimal;
+use crate::service::statistics::assets::{AssetSnapshot, AssetStatistic};
+use chrono::NaiveDateTime;
+use color_eyre::eyre::Error;
+use std::collections::HashSet;
+use std::fmt::Display;
+
+/// What to do when a rule is broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Reject,
+    Warn,
+    Allow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// Amount is zero or negative.
+    NonPositiveAmount,
+    /// Position goes below zero.
+    Oversell,
+    /// Trade without a known price.
+    MissingPrice,
+    /// Income on zero holdings.
+    NoHoldings,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.rule, self.message)
+    }
+}
+
+/// Operation validation rules.
+#[derive(Debug, Clone)]
+pub struct ValidationRules {
+    pub non_positive_amount: Action,
+    pub oversell: Action,
+    pub missing_price: Action,
+    pub no_holdings: Action,
+    /// Accounts allowed to hold short positions.
+    pub short_accounts: HashSet<String>,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        Self {
+            non_positive_amount: Action::Reject,
+            oversell: Action::Reject,
+            missing_price: Action::Reject,
+            no_holdings: Action::Warn,
+            short_accounts: HashSet::new(),
+        }
+    }
+}
+
+impl ValidationRules {
+    fn action(&self, rule: Rule) -> Action {
+        match rule {
+            Rule::NonPositiveAmount => self.non_positive_amount,
+            Rule::Oversell => self.oversell,
+            Rule::MissingPrice => self.missing_price,
+            Rule::NoHoldings => self.no_holdings,
+        }
+    }
+
+    /// Checks the operation against the asset history without it (`history`)
+    /// and with it (`replayed`).
+    /// Returns warnings, or an error for the first rejected violation.
+    pub fn check(
+        &self,
+        operation: &AssetOperation,
+        account: Option<&str>,
+        history: &AssetStatistic,
+        replayed: &AssetStatistic,
+    ) -> Result<Vec<Violation>, Error> {
+        let date = operation.operation_date;
+        let amount = operation.operation_amount;
+        let zero = Decimal::default();
+        let before = history
+            .get_latest_snapshot(date)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut violations = Vec::new();
+        if amount <= zero {
+            violations.push(Violation {
+                rule: Rule::NonPositiveAmount,
+                message: format!(
+                    "{:?} amount must be positive: {}",
+                    operation.operation_type, amount
+                ),
+            });
+        }
+
+        match operation.operation_type {
+            OperationType::Buy | OperationType::Sell | OperationType::DividendReinvestment
+                if before.price == zero =>
+            {
+                violations.push(Violation {
+                    rule: Rule::MissingPrice,
+                    message: format!("No price before {}", date),
+                });
+            }
+            _ => {}
+        }
+
+        match operation.operation_type {
+            OperationType::Dividend
+            | OperationType::Interest
+            | OperationType::DividendReinvestment
+                if before.quantity <= zero =>
+            {
+                violations.push(Violation {
+                    rule: Rule::NoHoldings,
+                    message: format!("{:?} on zero holdings", operation.operation_type),
+                });
+            }
+            _ => {}
+        }
+
+        let short_allowed = account.is_some_and(|a| self.short_accounts.contains(a));
+        if matches!(
+            operation.operation_type,
+            OperationType::Sell | OperationType::CashWithdrawal
+        ) && !short_allowed
+        {
+            let lowest = min_quantity(replayed.snapshots(), date);
+            let existing = min_quantity(history.snapshots(), date).min(zero);
+            if lowest < existing {
+                violations.push(Violation {
+                    rule: Rule::Oversell,
+                    message: format!(
+                        "{:?} of {} exceeds position of {}",
+                        operation.operation_type, amount, before.quantity
+                    ),
+                });
+            }
+        }
+
+        let mut warnings = Vec::new();
+        for violation in violations {
+            match self.action(violation.rule) {
+                Action::Reject => return Err(Error::msg(violation.to_string())),
+                Action::Warn => warnings.push(violation),
+                Action::Allow => {}
+            }
+        }
+        Ok(warnings)
+    }
+}
+
+/// Lowest quantity from `date` on.
+fn min_quantity(snapshots: &[AssetSnapshot], date: NaiveDateTime) -> Decimal {
+    snapshots
+        .iter()
+        .filter(|s| s.date >= date)
+        .map(|s| s.quantity)
+        .min()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dao::model::assets::Asset;
+    use crate::service::test_utils::op;
+
+    fn stat(ops: Vec<AssetOperation>) -> AssetStatistic {
+        AssetStatistic::new(
+            Asset {
+                id: 0,
+                name: "Test".to_string(),
+                ticker: "TST".to_string(),
+                ..Default::default()
+            },
+            ops,
+        )
+    }
+
+    fn check(
+        rules: &ValidationRules,
+        ops: Vec<AssetOperation>,
+        operation: AssetOperation,
+        account: Option<&str>,
+    ) -> Result<Vec<Violation>, Error> {
+        let history = stat(ops.clone());
+        let mut replayed = ops;
+        replayed.push(operation.clone());
+        rules.check(&operation, account, &history, &stat(replayed))
+    }
+
+    fn history() -> Vec<AssetOperation> {
+        vec![
+            op(OperationType::UpdatePrice, "10", "2020-01-01 00:00:00", 0),
+            op(OperationType::Buy, "10", "2020-01-02 00:00:00", 0),
+            op(OperationType::Sell, "8", "2020-01-10 00:00:00", 0),
+        ]
+    }
+
+    #[test]
+    fn test_oversell() {
+        let rules = ValidationRules::default();
+        let sell = op(OperationType::Sell, "2", "2020-01-11 00:00:00", 0);
+        assert!(check(&rules, history(), sell, None).unwrap().is_empty());
+
+        let sell = op(OperationType::Sell, "3", "2020-01-11 00:00:00", 0);
+        assert!(check(&rules, history(), sell, None).is_err());
+
+        // Backdated sell breaks the later one.
+        let sell = op(OperationType::Sell, "5", "2020-01-05 00:00:00", 0);
+        assert!(check(&rules, history(), sell, None).is_err());
+
+        let rules = ValidationRules {
+            short_accounts: HashSet::from(["margin".to_string()]),
+            ..Default::default()
+        };
+        let sell = op(OperationType::Sell, "3", "2020-01-11 00:00:00", 0);
+        assert!(check(&rules, history(), sell.clone(), Some("margin")).is_ok());
+        assert!(check(&rules, history(), sell, Some("broker")).is_err());
+    }
+
+    #[test]
+    fn test_missing_price_and_amount() {
+        let rules = ValidationRules::default();
+        let buy = op(OperationType::Buy, "1", "2020-01-01 00:00:00", 0);
+        assert!(check(&rules, vec![], buy, None).is_err());
+
+        let buy = op(OperationType::Buy, "0", "2020-01-03 00:00:00", 0);
+        assert!(check(&rules, history(), buy, None).is_err());
+
+        let rules = ValidationRules {
+            missing_price: Action::Allow,
+            ..Default::default()
+        };
+        let buy = op(OperationType::Buy, "1", "2020-01-01 00:00:00", 0);
+        assert!(check(&rules, vec![], buy, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_no_holdings() {
+        let rules = ValidationRules::default();
+        let dividend = op(OperationType::Dividend, "5", "2020-01-01 12:00:00", 0);
+        let warnings = check(&rules, history(), dividend, None).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule, Rule::NoHoldings);
+
+        let dividend = op(OperationType::Dividend, "5", "2020-01-03 00:00:00", 0);
+        assert!(check(&rules, history(), dividend, None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use color_eyre::eyre::Error;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Providers polled by the scheduler.
+#[derive(Debug, Deserialize)]
+pub struct SchedulerConfig {
+    /// Poll interval in seconds.
+    pub interval: u64,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    pub providers: Vec<ProviderConfig>,
+    /// Rate history compaction, disabled if not set.
+    pub retention: Option<RetentionConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetryConfig {
+    /// Attempts per instrument, including the first one.
+    pub attempts: u32,
+    /// Delay before the first retry in milliseconds. Doubled on each next retry.
+    pub delay: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            delay: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RetentionConfig {
+    /// Days of rate history kept at full resolution.
+    pub full: i64,
+    /// Days of rate history kept as daily closes. Older rates are kept as monthly closes.
+    pub daily: i64,
+    /// Compaction interval in seconds.
+    pub interval: u64,
+}
+
+/// Generic HTTP/JSON provider.
+#[derive(Debug, Deserialize)]
+pub struct ProviderConfig {
+    pub name: String,
+    /// Url template, `{ticker}` is replaced with the instrument ticker. Only plain http is supported.
+    pub url: String,
+    /// JSON pointer to the value in the response body, e.g. `/data/rate`.
+    pub pointer: String,
+    /// Request timeout in seconds.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Currencies to fetch rates for.
+    #[serde(default)]
+    pub currencies: Vec<String>,
+    /// Assets to fetch prices for.
+    #[serde(default)]
+    pub assets: Vec<String>,
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+impl SchedulerConfig {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let config = std::fs::read_to_string(path)?;
+        Self::parse(&config)
+    }
+
+    pub fn parse(config: &str) -> Result<Self, Error> {
+        let config: Self = serde_json::from_str(config)?;
+        for provider in &config.providers {
+            if !provider.url.starts_with("http://") {
+                return Err(Error::msg(format!(
+                    "Provider {} url must start with http://, TLS is not supported",
+                    provider.name
+                )));
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = |url: &str| {
+            format!(
+                r#"{{"interval": 60, "providers": [{{"name": "rates", "url": "{}", "pointer": "/rate"}}]}}"#,
+                url
+            )
+        };
+        let parsed = SchedulerConfig::parse(&config("http://localhost/{ticker}")).unwrap();
+        assert_eq!(parsed.providers[0].timeout, 10);
+        assert_eq!(parsed.retry.attempts, 3);
+        assert!(SchedulerConfig::parse(&config("https://localhost/{ticker}")).is_err());
+    }
+}
@@ -1,151 +1,46 @@
-use axum::{
-    error_handling::HandleErrorLayer,
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::{get, patch},
-    Json, Router,
-};
-use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
-    time::Duration,
-};
-use tower::{BoxError, ServiceBuilder};
-use tower_http::trace::TraceLayer;
+use clap::Parser;
+use color_eyre::eyre::Error;
+use finance_core::service::FinanceService;
+use scheduler::Scheduler;
+use sqlx::SqlitePool;
+use std::{path::PathBuf, sync::Arc};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use uuid::Uuid;
 
-#[tokio::main]
-async fn main() {
-    loop {}
-    // tracing_subscriber::registry()
-    //     .with(
-    //         tracing_subscriber::EnvFilter::try_from_default_env()
-    //             .unwrap_or_else(|_| "example_todos=debug,tower_http=debug".into()),
-    //     )
-    //     .with(tracing_subscriber::fmt::layer())
-    //     .init();
-
-    // let db = Db::default();
-
-    // // Compose the routes
-    // let app = Router::new()
-    //     .route("/todos", get(todos_index).post(todos_create))
-    //     .route("/todos/:id", patch(todos_update).delete(todos_delete))
-    //     // Add middleware to all routes
-    //     .layer(
-    //         ServiceBuilder::new()
-    //             .layer(HandleErrorLayer::new(|error: BoxError| async move {
-    //                 if error.is::<tower::timeout::error::Elapsed>() {
-    //                     Ok(StatusCode::REQUEST_TIMEOUT)
-    //                 } else {
-    //                     Err((
-    //                         StatusCode::INTERNAL_SERVER_ERROR,
-    //                         format!("Unhandled internal error: {error}"),
-    //                     ))
-    //                 }
-    //             }))
-    //             .timeout(Duration::from_secs(10))
-    //             .layer(TraceLayer::new_for_http())
-    //             .into_inner(),
-    //     )
-    //     .with_state(db);
-
-    // let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-    //     .await
-    //     .unwrap();
-    // tracing::debug!("listening on {}", listener.local_addr().unwrap());
-    // axum::serve(listener, app).await.unwrap();
-}
+mod config;
+mod provider;
+mod scheduler;
 
-// The query parameters for todos index
-#[derive(Debug, Deserialize, Default)]
-pub struct Pagination {
-    pub offset: Option<usize>,
-    pub limit: Option<usize>,
+#[derive(Parser)]
+struct Args {
+    #[arg(long = "db_url")]
+    db_url: String,
+    /// Rate and price providers config.
+    #[arg(long)]
+    providers: Option<PathBuf>,
 }
 
-async fn todos_index(
-    pagination: Option<Query<Pagination>>,
-    State(db): State<Db>,
-) -> impl IntoResponse {
-    let todos = db.read().unwrap();
-
-    let Query(pagination) = pagination.unwrap_or_default();
-
-    let todos = todos
-        .values()
-        .skip(pagination.offset.unwrap_or(0))
-        .take(pagination.limit.unwrap_or(usize::MAX))
-        .cloned()
-        .collect::<Vec<_>>();
-
-    Json(todos)
-}
-
-#[derive(Debug, Deserialize)]
-struct CreateTodo {
-    text: String,
-}
-
-async fn todos_create(State(db): State<Db>, Json(input): Json<CreateTodo>) -> impl IntoResponse {
-    let todo = Todo {
-        id: Uuid::new_v4(),
-        text: input.text,
-        completed: false,
-    };
-
-    db.write().unwrap().insert(todo.id, todo.clone());
-
-    (StatusCode::CREATED, Json(todo))
-}
-
-#[derive(Debug, Deserialize)]
-struct UpdateTodo {
-    text: Option<String>,
-    completed: Option<bool>,
-}
-
-async fn todos_update(
-    Path(id): Path<Uuid>,
-    State(db): State<Db>,
-    Json(input): Json<UpdateTodo>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let mut todo = db
-        .read()
-        .unwrap()
-        .get(&id)
-        .cloned()
-        .ok_or(StatusCode::NOT_FOUND)?;
-
-    if let Some(text) = input.text {
-        todo.text = text;
-    }
-
-    if let Some(completed) = input.completed {
-        todo.completed = completed;
-    }
-
-    db.write().unwrap().insert(todo.id, todo.clone());
-
-    Ok(Json(todo))
-}
-
-async fn todos_delete(Path(id): Path<Uuid>, State(db): State<Db>) -> impl IntoResponse {
-    if db.write().unwrap().remove(&id).is_some() {
-        StatusCode::NO_CONTENT
-    } else {
-        StatusCode::NOT_FOUND
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let args = Args::parse();
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "web=debug".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let pool = SqlitePool::connect(&args.db_url).await?;
+    let finance = Arc::new(FinanceService::new(pool));
+
+    if let Some(path) = args.providers {
+        let mut config = config::SchedulerConfig::load(&path)?;
+        if let Some(retention) = config.retention.take() {
+            tokio::spawn(scheduler::run_compaction(finance.clone(), retention));
+        }
+        tokio::spawn(Scheduler::from_config(finance.clone(), config).run());
     }
-}
-
-type Db = Arc<RwLock<HashMap<Uuid, Todo>>>;
 
-#[derive(Debug, Serialize, Clone)]
-struct Todo {
-    id: Uuid,
-    text: String,
-    completed: bool,
+    tokio::signal::ctrl_c().await?;
+    Ok(())
 }
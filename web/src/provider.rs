@@ -0,0 +1,163 @@
+use crate::config::ProviderConfig;
+use async_trait::async_trait;
+use color_eyre::eyre::Error;
+use finance_core::service::decimal::{Decimal, Rate};
+use finance_core::service::providers::{PriceProvider, RateProvider};
+use hyper::client::HttpConnector;
+use hyper::{Client, Uri};
+use serde_json::Value;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Fetches values with GET requests and reads them from the JSON response.
+pub struct HttpJsonProvider {
+    name: String,
+    url: String,
+    pointer: String,
+    timeout: Duration,
+    client: Client<HttpConnector>,
+}
+
+impl HttpJsonProvider {
+    pub fn new(config: &ProviderConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            url: config.url.clone(),
+            pointer: config.pointer.clone(),
+            timeout: Duration::from_secs(config.timeout),
+            client: Client::new(),
+        }
+    }
+
+    async fn fetch<T: FromStr<Err = Error>>(&self, ticker: &str) -> Result<T, Error> {
+        let uri = Uri::from_str(&self.url.replace("{ticker}", ticker))?;
+        let response = tokio::time::timeout(self.timeout, self.client.get(uri)).await??;
+        if !response.status().is_success() {
+            return Err(Error::msg(format!(
+                "{} responded with {}",
+                self.name,
+                response.status()
+            )));
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        parse_value(&body, &self.pointer)
+    }
+}
+
+fn parse_value<T: FromStr<Err = Error>>(body: &[u8], pointer: &str) -> Result<T, Error> {
+    let json: Value = serde_json::from_slice(body)?;
+    match json.pointer(pointer) {
+        Some(Value::Number(value)) => T::from_str(&value.to_string()),
+        Some(Value::String(value)) => T::from_str(value),
+        Some(_) => Err(Error::msg(format!("{} is not a number", pointer))),
+        None => Err(Error::msg(format!("{} not found", pointer))),
+    }
+}
+
+#[async_trait]
+impl RateProvider for HttpJsonProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch_rate(&self, ticker: &str) -> Result<Rate, Error> {
+        self.fetch(ticker).await
+    }
+}
+
+#[async_trait]
+impl PriceProvider for HttpJsonProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch_price(&self, ticker: &str) -> Result<Decimal, Error> {
+        self.fetch(ticker).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Path;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+    use finance_core::service::decimal::{dec, rate};
+    use std::net::SocketAddr;
+
+    /// Serves rates on a free local port and returns its address.
+    fn serve() -> SocketAddr {
+        let app = Router::new()
+            .route(
+                "/rate/:ticker",
+                get(|Path(ticker): Path<String>| async move {
+                    match ticker.as_str() {
+                        "JPY" => Ok(r#"{"data": {"rate": 0.0067}}"#),
+                        _ => Err(StatusCode::NOT_FOUND),
+                    }
+                }),
+            )
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    r#"{"data": {"rate": 0.0067}}"#
+                }),
+            )
+            .route("/down", get(|| async { StatusCode::SERVICE_UNAVAILABLE }))
+            .route("/bad", get(|| async { "<html></html>" }));
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    fn provider(addr: SocketAddr, path: &str) -> HttpJsonProvider {
+        HttpJsonProvider {
+            name: "stub".to_string(),
+            url: format!("http://{}{}", addr, path),
+            pointer: "/data/rate".to_string(),
+            timeout: Duration::from_millis(200),
+            client: Client::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let body = br#"{"data": {"rate": 96.5044, "price": "12.5"}}"#;
+        assert_eq!(
+            parse_value::<Rate>(body, "/data/rate").unwrap(),
+            rate("96.5044")
+        );
+        assert_eq!(
+            parse_value::<Decimal>(body, "/data/price").unwrap(),
+            dec("12.5")
+        );
+        assert!(parse_value::<Rate>(body, "/data").is_err());
+        assert!(parse_value::<Rate>(body, "/rate").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let addr = serve();
+        let value = provider(addr, "/rate/{ticker}").fetch_rate("JPY").await;
+        assert_eq!(value.unwrap(), rate("0.0067"));
+        assert!(provider(addr, "/rate/{ticker}")
+            .fetch_rate("XXX")
+            .await
+            .is_err());
+
+        let err = provider(addr, "/down").fetch_rate("JPY").await;
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "stub responded with 503 Service Unavailable"
+        );
+
+        let err = provider(addr, "/slow").fetch_rate("JPY").await;
+        assert!(err.unwrap_err().is::<tokio::time::error::Elapsed>());
+
+        assert!(provider(addr, "/bad").fetch_rate("JPY").await.is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use crate::config::{RetentionConfig, SchedulerConfig};
+use crate::provider::HttpJsonProvider;
+use color_eyre::eyre::Error;
+use finance_core::dao::model::currency::RateSource;
+use finance_core::dao::model::operations::OperationType;
+use finance_core::service::providers::{PriceProvider, RateProvider, ASSET_KIND, CURRENCY_KIND};
+use finance_core::service::retention::RetentionPolicy;
+use finance_core::service::FinanceService;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before the retry following the given attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.delay * 2u32.saturating_pow(attempt.saturating_sub(1))
+    }
+
+    /// Runs `fetch` until it succeeds or attempts are exhausted.
+    /// Returns the number of attempts made with the last result.
+    async fn run<T, F, Fut>(&self, mut fetch: F) -> (u32, Result<T, Error>)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match fetch().await {
+                Ok(value) => return (attempt, Ok(value)),
+                Err(err) if attempt >= self.attempts => return (attempt, Err(err)),
+                Err(err) => {
+                    tracing::warn!("fetch attempt {} failed: {}", attempt, err);
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Job {
+    Rate {
+        ticker: String,
+        provider: Arc<dyn RateProvider>,
+    },
+    Price {
+        ticker: String,
+        provider: Arc<dyn PriceProvider>,
+    },
+}
+
+/// Polls providers and stores fetched rates and prices.
+pub struct Scheduler {
+    finance: Arc<FinanceService>,
+    interval: Duration,
+    retry: RetryPolicy,
+    jobs: Vec<Job>,
+}
+
+impl Scheduler {
+    pub fn new(finance: Arc<FinanceService>, interval: Duration, retry: RetryPolicy) -> Self {
+        Self {
+            finance,
+            interval,
+            retry,
+            jobs: Vec::new(),
+        }
+    }
+
+    pub fn from_config(finance: Arc<FinanceService>, config: SchedulerConfig) -> Self {
+        let mut scheduler = Self::new(
+            finance,
+            Duration::from_secs(config.interval),
+            RetryPolicy {
+                attempts: config.retry.attempts.max(1),
+                delay: Duration::from_millis(config.retry.delay),
+            },
+        );
+        for provider_config in config.providers {
+            let provider = Arc::new(HttpJsonProvider::new(&provider_config));
+            for ticker in provider_config.currencies {
+                scheduler.add_rate(ticker, provider.clone());
+            }
+            for ticker in provider_config.assets {
+                scheduler.add_price(ticker, provider.clone());
+            }
+        }
+        scheduler
+    }
+
+    pub fn add_rate(&mut self, ticker: String, provider: Arc<dyn RateProvider>) {
+        self.jobs.push(Job::Rate { ticker, provider });
+    }
+
+    pub fn add_price(&mut self, ticker: String, provider: Arc<dyn PriceProvider>) {
+        self.jobs.push(Job::Price { ticker, provider });
+    }
+
+    pub async fn run(self) {
+        let mut timer = tokio::time::interval(self.interval);
+        loop {
+            timer.tick().await;
+            self.poll().await;
+        }
+    }
+
+    /// Runs every job concurrently, so retries of one provider don't delay the others.
+    pub async fn poll(&self) {
+        let mut tasks = JoinSet::new();
+        for job in &self.jobs {
+            tasks.spawn(poll_job(self.finance.clone(), self.retry, job.clone()));
+        }
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => tracing::error!("failed to store fetch result: {}", err),
+                Err(err) => tracing::error!("fetch job failed: {}", err),
+            }
+        }
+    }
+}
+
+async fn poll_job(finance: Arc<FinanceService>, retry: RetryPolicy, job: Job) -> Result<(), Error> {
+    let (kind, ticker, provider, attempts, result) = match &job {
+        Job::Rate { ticker, provider } => {
+            let (attempts, result) = retry.run(|| provider.fetch_rate(ticker)).await;
+            let result = match result {
+                Ok(rate) => {
+                    let source = RateSource::Provider(provider.name().to_string());
+                    finance
+                        .add_currency_rate(ticker.clone(), rate, source)
+                        .await
+                }
+                Err(err) => Err(err),
+            };
+            (CURRENCY_KIND, ticker, provider.name(), attempts, result)
+        }
+        Job::Price { ticker, provider } => {
+            let (attempts, result) = retry.run(|| provider.fetch_price(ticker)).await;
+            let result = match result {
+                Ok(price) => finance
+                    .add_operation(ticker.clone(), price, OperationType::UpdatePrice)
+                    .await
+                    .map(|_| ()),
+                Err(err) => Err(err),
+            };
+            (ASSET_KIND, ticker, provider.name(), attempts, result)
+        }
+    };
+
+    if let Err(err) = &result {
+        tracing::error!("{} {} from {}: {}", kind, ticker, provider, err);
+    }
+    finance
+        .fetch_status
+        .record(
+            kind,
+            ticker,
+            provider,
+            attempts,
+            result.err().map(|err| err.to_string()),
+        )
+        .await
+}
+
+/// Periodically compacts rate history.
+pub async fn run_compaction(finance: Arc<FinanceService>, config: RetentionConfig) {
+    let policy = RetentionPolicy {
+        full: chrono::Duration::days(config.full),
+        daily: chrono::Duration::days(config.daily),
+    };
+    let mut timer = tokio::time::interval(Duration::from_secs(config.interval));
+    loop {
+        timer.tick().await;
+        match finance.compact_rates(policy).await {
+            Ok(report) => {
+                for currency in report.currencies {
+                    tracing::info!(
+                        "{}: removed {} rates in {:?}, kept {} referenced",
+                        currency.ticker,
+                        currency.removed,
+                        currency.range,
+                        currency.referenced
+                    );
+                }
+            }
+            Err(err) => tracing::error!("rate compaction failed: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use finance_core::service::decimal::{rate, Rate};
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tokio::sync::Notify;
+
+    /// Fails the given number of times, then returns the value.
+    struct StubProvider {
+        failures: u32,
+        calls: AtomicU32,
+        value: Rate,
+    }
+
+    impl StubProvider {
+        fn new(failures: u32, value: &str) -> Arc<Self> {
+            Arc::new(Self {
+                failures,
+                calls: AtomicU32::new(0),
+                value: rate(value),
+            })
+        }
+
+        async fn fetch(&self) -> Result<Rate, Error> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if call <= self.failures {
+                return Err(Error::msg(format!("failure {}", call)));
+            }
+            Ok(self.value)
+        }
+    }
+
+    #[async_trait]
+    impl RateProvider for StubProvider {
+        fn name(&self) -> &str {
+            "stub"
+        }
+
+        async fn fetch_rate(&self, _ticker: &str) -> Result<Rate, Error> {
+            self.fetch().await
+        }
+    }
+
+    /// Waits for the gate unless it opens it, so it completes only when polled with
+    /// an opening provider at the same time.
+    struct GateProvider {
+        gate: Arc<Notify>,
+        opens: bool,
+    }
+
+    #[async_trait]
+    impl RateProvider for GateProvider {
+        fn name(&self) -> &str {
+            "gate"
+        }
+
+        async fn fetch_rate(&self, _ticker: &str) -> Result<Rate, Error> {
+            if self.opens {
+                self.gate.notify_one();
+            } else {
+                self.gate.notified().await;
+            }
+            Ok(rate("1.2"))
+        }
+    }
+
+    fn policy(attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            attempts,
+            delay: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let retry = RetryPolicy {
+            attempts: 4,
+            delay: Duration::from_millis(100),
+        };
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(200));
+        assert_eq!(retry.backoff(3), Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let provider = StubProvider::new(2, "1.5");
+        let (attempts, result) = policy(3).run(|| provider.fetch()).await;
+        assert_eq!(attempts, 3);
+        assert_eq!(result.unwrap(), rate("1.5"));
+
+        let provider = StubProvider::new(5, "1.5");
+        let (attempts, result) = policy(3).run(|| provider.fetch()).await;
+        assert_eq!(attempts, 3);
+        assert_eq!(result.unwrap_err().to_string(), "failure 3");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_poll() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("../core/migrations")
+            .run(&pool)
+            .await
+            .unwrap();
+        let finance = Arc::new(FinanceService::new(pool));
+        finance.seed_currency("EUR", rate("1.1")).await.unwrap();
+        finance.seed_currency("GBP", rate("1.3")).await.unwrap();
+
+        let mut scheduler = Scheduler::new(finance.clone(), Duration::from_secs(60), policy(2));
+        scheduler.add_rate("EUR".to_string(), StubProvider::new(1, "1.2"));
+        scheduler.add_rate("GBP".to_string(), StubProvider::new(2, "1.4"));
+        scheduler.poll().await;
+
+        let eur = finance
+            .fetch_status
+            .get(CURRENCY_KIND, "EUR")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(eur.provider, "stub");
+        assert_eq!(eur.attempts, 2);
+        assert_eq!(eur.error, None);
+        assert!(eur.last_success.is_some());
+
+        let gbp = finance
+            .fetch_status
+            .get(CURRENCY_KIND, "GBP")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(gbp.attempts, 2);
+        assert_eq!(gbp.error.as_deref(), Some("failure 2"));
+        assert_eq!(gbp.last_success, None);
+    }
+
+    #[tokio::test]
+    async fn test_poll_concurrent() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("../core/migrations")
+            .run(&pool)
+            .await
+            .unwrap();
+        let finance = Arc::new(FinanceService::new(pool));
+        finance.seed_currency("EUR", rate("1.1")).await.unwrap();
+        finance.seed_currency("GBP", rate("1.3")).await.unwrap();
+
+        let gate = Arc::new(Notify::new());
+        let mut scheduler = Scheduler::new(finance.clone(), Duration::from_secs(60), policy(1));
+        scheduler.add_rate(
+            "EUR".to_string(),
+            Arc::new(GateProvider {
+                gate: gate.clone(),
+                opens: false,
+            }),
+        );
+        scheduler.add_rate(
+            "GBP".to_string(),
+            Arc::new(GateProvider { gate, opens: true }),
+        );
+        tokio::time::timeout(Duration::from_secs(5), scheduler.poll())
+            .await
+            .unwrap();
+
+        for ticker in ["EUR", "GBP"] {
+            let status = finance
+                .fetch_status
+                .get(CURRENCY_KIND, ticker)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(status.error, None);
+        }
+    }
+}